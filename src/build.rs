@@ -0,0 +1,510 @@
+//! Build Ruby from source
+//!
+//! For preview releases, patched Rubies and platforms without a prebuilt
+//! binary. The source tarball is configured with the final install prefix,
+//! installed into a staging directory via `DESTDIR`, then goes through the
+//! same checks and swap as a binary install.
+
+use crate::download::{self, RetryPolicy};
+use crate::{paths, platform};
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use tar::Archive;
+
+/// Where official Ruby source tarballs are published
+const RUBY_SOURCE_URL: &str = "https://cache.ruby-lang.org/pub/ruby";
+
+/// Lines of build output shown when a step fails
+const LOG_TAIL_LINES: usize = 20;
+
+/// A tool or header needed to compile Ruby
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub name: &'static str,
+    pub found: bool,
+}
+
+/// Headers Ruby's default extensions need (openssl, psych, zlib)
+const HEADERS: &[(&str, &str)] = &[
+    ("openssl", "openssl/ssl.h"),
+    ("libyaml", "yaml.h"),
+    ("zlib", "zlib.h"),
+];
+
+/// Check for a C compiler, make and the library headers Ruby needs
+pub fn check_dependencies() -> Vec<Dependency> {
+    let include_dirs = include_dirs();
+    let mut deps = vec![
+        Dependency {
+            name: "C compiler",
+            found: ["cc", "gcc", "clang"].iter().any(|cmd| on_path(cmd)),
+        },
+        Dependency {
+            name: "make",
+            found: on_path("make"),
+        },
+    ];
+    for (name, header) in HEADERS {
+        deps.push(Dependency {
+            name,
+            found: find_header(header, &include_dirs).is_some(),
+        });
+    }
+    deps
+}
+
+/// Package manager command that installs the build dependencies
+pub fn install_hint() -> &'static str {
+    if platform::detect_os() == "darwin" {
+        "xcode-select --install && brew install openssl@3 libyaml"
+    } else if platform::detect_libc() == Some(platform::Libc::Musl) {
+        "apk add build-base openssl-dev yaml-dev zlib-dev"
+    } else if Path::new("/etc/redhat-release").exists() {
+        "dnf install gcc make openssl-devel libyaml-devel zlib-devel"
+    } else {
+        "apt-get install build-essential libssl-dev libyaml-dev zlib1g-dev"
+    }
+}
+
+/// Directories searched for headers: CPATH/C_INCLUDE_PATH, then the usual
+/// system and Homebrew locations
+fn include_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    for var in ["CPATH", "C_INCLUDE_PATH"] {
+        if let Some(value) = env::var_os(var) {
+            dirs.extend(env::split_paths(&value));
+        }
+    }
+    for prefix in homebrew_prefixes() {
+        dirs.push(prefix.join("include"));
+    }
+    dirs.extend(
+        [
+            "/usr/local/include",
+            "/usr/include",
+            "/Library/Developer/CommandLineTools/SDKs/MacOSX.sdk/usr/include",
+        ]
+        .iter()
+        .map(PathBuf::from),
+    );
+    dirs
+}
+
+/// Keg-only Homebrew prefixes that configure has to be pointed at
+fn homebrew_prefixes() -> Vec<PathBuf> {
+    let mut prefixes = vec![];
+    for brew in ["/opt/homebrew/opt", "/usr/local/opt"] {
+        for formula in ["openssl@3", "libyaml", "zlib"] {
+            let prefix = Path::new(brew).join(formula);
+            if prefix.is_dir() {
+                prefixes.push(prefix);
+            }
+        }
+    }
+    prefixes
+}
+
+fn find_header(header: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    dirs.iter()
+        .map(|dir| dir.join(header))
+        .find(|path| path.is_file())
+}
+
+fn on_path(cmd: &str) -> bool {
+    env::var_os("PATH")
+        .map(|path| env::split_paths(&path).any(|dir| dir.join(cmd).is_file()))
+        .unwrap_or(false)
+}
+
+/// SHA-256 of a source tarball as listed in ruby-lang.org's `index.txt`
+///
+/// Each line holds a release's name, url, sha1, sha256 and sha512,
+/// separated by tabs.
+fn published_sha256(index: &str, filename: &str) -> Option<String> {
+    index.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields.as_slice() {
+            [_, url, _, sha256, ..] if url.rsplit('/').next() == Some(filename) => {
+                Some(sha256.to_string())
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Default download URL for a version's source tarball
+fn source_url(version: &str) -> String {
+    format!(
+        "{}/{}/ruby-{}.tar.gz",
+        RUBY_SOURCE_URL,
+        download::version_series(version),
+        version
+    )
+}
+
+/// Arguments passed to `./configure`
+fn configure_args(prefix: &Path, homebrew: &[PathBuf]) -> Vec<String> {
+    let mut args = vec![
+        format!("--prefix={}", prefix.display()),
+        "--disable-install-doc".to_string(),
+    ];
+    for dir in homebrew {
+        let formula = dir.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let flag = match formula {
+            "openssl@3" => "openssl",
+            "libyaml" => "libyaml",
+            "zlib" => "zlib",
+            _ => continue,
+        };
+        args.push(format!("--with-{}-dir={}", flag, dir.display()));
+    }
+    args
+}
+
+/// Name of the single top-level directory in a source tarball
+fn tarball_root(tarball: &Path) -> Result<String> {
+    let file = File::open(tarball)
+        .with_context(|| format!("Failed to open source tarball: {}", tarball.display()))?;
+    let mut archive = Archive::new(GzDecoder::new(BufReader::new(file)));
+    let mut entries = archive
+        .entries()
+        .with_context(|| format!("Failed to read source tarball: {}", tarball.display()))?;
+
+    let entry = match entries.next() {
+        Some(entry) => entry.context("Failed to read source tarball entry")?,
+        None => bail!("Source tarball is empty: {}", tarball.display()),
+    };
+    let path = entry.path()?;
+    match path.components().next() {
+        Some(Component::Normal(root)) => Ok(root.to_string_lossy().to_string()),
+        _ => bail!(
+            "Source tarball has an unexpected layout: {}",
+            tarball.display()
+        ),
+    }
+}
+
+/// Read a source tarball to the end, so a truncated or corrupt file fails
+/// here instead of halfway through the build
+fn check_archive(tarball: &Path) -> Result<()> {
+    let file = File::open(tarball)
+        .with_context(|| format!("Failed to open source tarball: {}", tarball.display()))?;
+    let mut archive = Archive::new(GzDecoder::new(BufReader::new(file)));
+    let entries = archive
+        .entries()
+        .with_context(|| format!("Failed to read source tarball: {}", tarball.display()))?;
+    for entry in entries {
+        entry.with_context(|| format!("Source tarball is corrupt: {}", tarball.display()))?;
+    }
+
+    // Read up to the gzip trailer so its CRC is checked too
+    io::copy(&mut archive.into_inner(), &mut io::sink())
+        .with_context(|| format!("Source tarball is corrupt: {}", tarball.display()))?;
+    Ok(())
+}
+
+/// Check a downloaded source tarball before it is moved into the cache
+///
+/// The ruby-lang.org tarball must match the SHA-256 published in
+/// `index.txt`. A custom URL has no published checksum, so it only has to
+/// read as a complete gzipped tar.
+fn verify_source(tarball: &Path, version: &str, official: bool) -> Result<()> {
+    if official {
+        println!("Verifying checksum...");
+        let index_url = format!("{}/index.txt", RUBY_SOURCE_URL);
+        let index =
+            download::fetch_text(&index_url).context("Failed to download Ruby source checksums")?;
+        let filename = format!("ruby-{}.tar.gz", version);
+        let Some(expected) = published_sha256(&index, &filename) else {
+            bail!("No published checksum for {} in {}", filename, index_url);
+        };
+        if download::file_sha256(tarball)? != expected {
+            bail!("Checksum verification failed. The download may be corrupted.");
+        }
+    }
+    check_archive(tarball)
+}
+
+/// Locate the source tarball, downloading it into the cache if needed
+fn fetch_source(version: &str, source: Option<&str>) -> Result<PathBuf> {
+    if let Some(source) = source {
+        let path = Path::new(source.strip_prefix("file://").unwrap_or(source));
+        if path.is_file() {
+            return Ok(path.to_path_buf());
+        }
+        if !source.contains("://") {
+            bail!("Source tarball not found: {}", source);
+        }
+    }
+
+    // Only the default ruby-lang.org tarball is reused; an explicit URL may
+    // point at a patched build of the same version, so it gets its own file
+    let cached = match source {
+        None => paths::cache_dir().join(format!("ruby-{}-source.tar.gz", version)),
+        Some(_) => paths::cache_dir().join(format!("ruby-{}-custom-source.tar.gz", version)),
+    };
+    if cached.exists() && source.is_none() {
+        match check_archive(&cached) {
+            Ok(()) => {
+                println!("Using cached {}...", cached.display());
+                return Ok(cached);
+            }
+            Err(_) => {
+                println!("Cached source tarball is corrupt, downloading again...");
+                fs::remove_file(&cached)?;
+            }
+        }
+    }
+    if download::is_offline() {
+        bail!(
+            "Ruby {} source is not in the download cache and offline mode is on.\n\
+             Pass a local tarball with --source <path>",
+            version
+        );
+    }
+
+    let url = source
+        .map(str::to_string)
+        .unwrap_or_else(|| source_url(version));
+    println!("Downloading {}...", url);
+    let part = download::part_path(&cached);
+    download::download_with_progress(&url, &part, &RetryPolicy::default())?;
+    if let Err(e) = verify_source(&part, version, source.is_none()) {
+        let _ = fs::remove_file(&part);
+        return Err(e);
+    }
+    fs::rename(&part, &cached)
+        .with_context(|| format!("Failed to move download into cache: {}", cached.display()))?;
+    Ok(cached)
+}
+
+/// Run one build step, appending its output to `log`
+fn run_step(label: &str, program: &str, args: &[String], dir: &Path, log: &Path) -> Result<()> {
+    println!("{}...", label);
+    let out = File::options()
+        .create(true)
+        .append(true)
+        .open(log)
+        .with_context(|| format!("Failed to open build log: {}", log.display()))?;
+
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(out.try_clone()?)
+        .stderr(out)
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    if !status.success() {
+        let output = fs::read_to_string(log).unwrap_or_default();
+        let lines: Vec<&str> = output.lines().collect();
+        let tail = lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].join("\n");
+        bail!(
+            "{} failed ({}):\n{}\n\nFull log: {}",
+            label,
+            status,
+            tail,
+            log.display()
+        );
+    }
+    Ok(())
+}
+
+/// Compile and install a Ruby version from source
+pub fn build_ruby(
+    version: &str,
+    source: Option<&str>,
+    jobs: Option<usize>,
+    force: bool,
+) -> Result<()> {
+    let dest = paths::ruby_version_dir(version);
+    let _ = download::cleanup_stale_installs(&paths::ruby_dir());
+    let _lock = download::install_lock(version)?;
+    if dest.exists() && !force {
+        println!(
+            "Ruby {} is already installed at {}",
+            version,
+            dest.display()
+        );
+        return Ok(());
+    }
+
+    let missing: Vec<_> = check_dependencies()
+        .into_iter()
+        .filter(|dep| !dep.found)
+        .map(|dep| dep.name)
+        .collect();
+    if !missing.is_empty() {
+        bail!(
+            "Missing build dependencies: {}\nInstall them with: {}\nSee: railsup doctor",
+            missing.join(", "),
+            install_hint()
+        );
+    }
+
+    paths::ensure_dirs()?;
+    let tarball = fetch_source(version, source)?;
+    let root = tarball_root(&tarball)?;
+
+    let build_dir =
+        paths::cache_dir()
+            .join("build")
+            .join(format!("ruby-{}-{}", version, std::process::id()));
+    if build_dir.exists() {
+        fs::remove_dir_all(&build_dir)?;
+    }
+    println!("Extracting {}...", tarball.display());
+    download::extract_tarball(&tarball, &build_dir, &root)?;
+
+    let src_dir = build_dir.join(&root);
+    let log = build_dir.join("build.log");
+    let jobs = jobs
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1);
+
+    let result = download::install_staged(&dest, |staging, _name| {
+        run_step(
+            "Configuring",
+            "./configure",
+            &configure_args(&dest, &homebrew_prefixes()),
+            &src_dir,
+            &log,
+        )?;
+        run_step(
+            &format!("Compiling with {} job(s)", jobs),
+            "make",
+            &[format!("-j{}", jobs)],
+            &src_dir,
+            &log,
+        )?;
+        run_step(
+            "Installing",
+            "make",
+            &[
+                "install".to_string(),
+                format!("DESTDIR={}", staging.display()),
+            ],
+            &src_dir,
+            &log,
+        )?;
+
+        // DESTDIR re-roots the absolute prefix under the staging directory
+        let relative = dest.strip_prefix("/").unwrap_or(&dest);
+        Ok(staging.join(relative))
+    });
+
+    // Keep the build tree around for inspection when something went wrong
+    if result.is_ok() {
+        let _ = fs::remove_dir_all(&build_dir);
+        if let Some(parent) = build_dir.parent() {
+            let _ = fs::remove_dir(parent);
+        }
+    }
+    result?;
+
+    fs::create_dir_all(paths::gems_version_dir(version))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tempfile::TempDir;
+
+    #[test]
+    fn source_url_uses_series_directory() {
+        assert_eq!(
+            source_url("3.4.1"),
+            "https://cache.ruby-lang.org/pub/ruby/3.4/ruby-3.4.1.tar.gz"
+        );
+    }
+
+    #[test]
+    fn configure_args_point_at_homebrew_kegs() {
+        let args = configure_args(
+            Path::new("/home/me/.railsup/ruby/ruby-3.4.1"),
+            &[PathBuf::from("/opt/homebrew/opt/openssl@3")],
+        );
+        assert_eq!(args[0], "--prefix=/home/me/.railsup/ruby/ruby-3.4.1");
+        assert!(args.contains(&"--disable-install-doc".to_string()));
+        assert!(args.contains(&"--with-openssl-dir=/opt/homebrew/opt/openssl@3".to_string()));
+    }
+
+    #[test]
+    fn find_header_searches_in_order() {
+        let temp = TempDir::new().unwrap();
+        let first = temp.path().join("first");
+        let second = temp.path().join("second");
+        fs::create_dir_all(second.join("openssl")).unwrap();
+        fs::write(second.join("openssl/ssl.h"), "").unwrap();
+
+        let dirs = vec![first, second.clone()];
+        assert_eq!(
+            find_header("openssl/ssl.h", &dirs),
+            Some(second.join("openssl/ssl.h"))
+        );
+        assert_eq!(find_header("yaml.h", &dirs), None);
+    }
+
+    #[test]
+    fn published_sha256_matches_tarball_name() {
+        let index = "name\turl\tsha1\tsha256\tsha512\n\
+            ruby-3.4.1\thttps://cache.ruby-lang.org/pub/ruby/3.4/ruby-3.4.1.tar.gz\ts1\tgz256\ts512\n\
+            ruby-3.4.1\thttps://cache.ruby-lang.org/pub/ruby/3.4/ruby-3.4.1.tar.xz\ts1\txz256\ts512\n";
+        assert_eq!(
+            published_sha256(index, "ruby-3.4.1.tar.gz"),
+            Some("gz256".to_string())
+        );
+        assert_eq!(published_sha256(index, "ruby-3.4.2.tar.gz"), None);
+    }
+
+    #[test]
+    fn check_archive_rejects_truncated_tarball() {
+        let temp = TempDir::new().unwrap();
+        let tarball = temp.path().join("ruby.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            File::create(&tarball).unwrap(),
+            Compression::default(),
+        ));
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "ruby-3.4.1/configure", &data[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        check_archive(&tarball).unwrap();
+
+        let bytes = fs::read(&tarball).unwrap();
+        fs::write(&tarball, &bytes[..bytes.len() - 16]).unwrap();
+        assert!(check_archive(&tarball).is_err());
+    }
+
+    #[test]
+    fn tarball_root_reads_top_level_directory() {
+        let temp = TempDir::new().unwrap();
+        let tarball = temp.path().join("ruby.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            File::create(&tarball).unwrap(),
+            Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "ruby-3.5.0-preview1/configure", &[][..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        assert_eq!(tarball_root(&tarball).unwrap(), "ruby-3.5.0-preview1");
+    }
+}
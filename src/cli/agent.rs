@@ -2,8 +2,9 @@
 
 use crate::cli::ruby::list_installed_versions;
 use crate::config::Config;
+use crate::paths;
+use crate::ruby::{find_project_ruby, resolve_installed};
 use std::env;
-use std::path::Path;
 
 /// Output context for AI agents
 pub fn run() {
@@ -29,8 +30,9 @@ fn detect_project_context() -> Option<ProjectContext> {
         .and_then(|n| n.to_str())
         .map(|s| s.to_string());
 
-    // Check for Ruby version (railsup.toml, .ruby-version, or .tool-versions)
-    let ruby_version = find_project_ruby(&current_dir);
+    // Check for Ruby version (railsup.toml, .ruby-version, .tool-versions, or Gemfile)
+    let ruby_version = find_project_ruby(&current_dir)
+        .map(|found| (found.version, found.source.label().to_string()));
 
     Some(ProjectContext {
         app_name,
@@ -47,57 +49,23 @@ struct ProjectContext {
 
 /// Detect if shell integration is active (railsup Ruby is in PATH)
 fn detect_shell_integration() -> bool {
-    let path = env::var("PATH").unwrap_or_default();
-    path.contains(".railsup/ruby/")
-}
-
-/// Find project Ruby version from config files
-fn find_project_ruby(dir: &Path) -> Option<(String, String)> {
-    // 1. railsup.toml
-    let railsup_toml = dir.join("railsup.toml");
-    if railsup_toml.exists() {
-        if let Ok(content) = std::fs::read_to_string(&railsup_toml) {
-            if let Ok(config) = toml::from_str::<toml::Table>(&content) {
-                if let Some(ruby) = config.get("ruby").and_then(|v| v.as_str()) {
-                    return Some((ruby.to_string(), "railsup.toml".to_string()));
-                }
-            }
-        }
-    }
-
-    // 2. .ruby-version
-    let ruby_version_file = dir.join(".ruby-version");
-    if ruby_version_file.exists() {
-        if let Ok(content) = std::fs::read_to_string(&ruby_version_file) {
-            let version = content.trim().trim_start_matches("ruby-").to_string();
-            if !version.is_empty() {
-                return Some((version, ".ruby-version".to_string()));
-            }
-        }
-    }
-
-    // 3. .tool-versions (asdf)
-    let tool_versions = dir.join(".tool-versions");
-    if tool_versions.exists() {
-        if let Ok(content) = std::fs::read_to_string(&tool_versions) {
-            for line in content.lines() {
-                if line.starts_with("ruby ") {
-                    let version = line.trim_start_matches("ruby ").trim().to_string();
-                    return Some((version, ".tool-versions".to_string()));
-                }
-            }
-        }
-    }
-
-    None
+    let path = env::var_os("PATH").unwrap_or_default();
+    let ruby_dir = paths::ruby_dir();
+    env::split_paths(&path).any(|p| p.starts_with(&ruby_dir))
 }
 
 fn build_context() -> String {
     let version = env!("CARGO_PKG_VERSION");
     let installed = list_installed_versions().unwrap_or_default();
-    let default_ruby = Config::load()
-        .ok()
-        .and_then(|c| c.default_ruby().map(|s| s.to_string()));
+    // Aliases and channels ("work", "latest") are shown with their version
+    let default_ruby = Config::load().ok().and_then(|config| {
+        let name = config.default_ruby()?;
+        Some(match resolve_installed(&config, name) {
+            Ok(version) if version == name => version,
+            Ok(version) => format!("{} ({})", version, name),
+            Err(_) => format!("{} (not installed)", name),
+        })
+    });
 
     // Detect shell integration
     let shell_integrated = detect_shell_integration();
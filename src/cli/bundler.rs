@@ -650,6 +650,7 @@ mod tests {
     // ==================== build_full_env tests ====================
 
     #[test]
+    #[allow(clippy::unnecessary_get_then_check)]
     fn build_full_env_sets_bundle_gemfile() {
         let _guard = ENV_MUTEX.lock().unwrap();
         let dir = tempdir().unwrap();
@@ -822,6 +823,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_get_then_check)]
     fn build_full_env_respects_opt_out() {
         let _guard = ENV_MUTEX.lock().unwrap();
         let dir = tempdir().unwrap();
@@ -4,28 +4,59 @@ use crate::cli::bundler::{
     BundleContext,
 };
 use crate::cli::new::ensure_ruby_available;
+use crate::config::{ProjectConfig, RestartConfig, RestartPolicy};
+use crate::dotenv::{self, DotenvVar};
 use crate::paths;
-use crate::util::ui;
+use crate::usage;
+use crate::util::{port as ports, ui};
 use anyhow::{bail, Result};
 use std::collections::HashMap;
 use std::env;
-use std::io::{BufRead, BufReader, IsTerminal};
-use std::path::Path;
-use std::process::{Child, Command, Stdio};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::io::{BufRead, BufReader, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
 
+/// Port used when neither `--port` nor `$PORT` is set
+const DEFAULT_PORT: u16 = 3000;
+
+/// Procfile process that serves the app unless railsup.toml names another
+const DEFAULT_WEB_PROCESS: &str = "web";
+
+/// Gap between the ports of consecutive Procfile processes (as in foreman)
+const PORT_STEP: u16 = 100;
+
 /// Timeout for graceful shutdown before force kill
 const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
 
-/// Type alias for child process with output thread handles
-type ChildWithHandles = (
-    Child,
-    Option<thread::JoinHandle<()>>,
-    Option<thread::JoinHandle<()>>,
-);
+/// Signal sent to every process group when one process exits
+#[cfg(unix)]
+const SHUTDOWN_SIGNAL: i32 = libc::SIGTERM;
+#[cfg(not(unix))]
+const SHUTDOWN_SIGNAL: i32 = 15;
+
+/// Signal for whatever is left after `SHUTDOWN_TIMEOUT`
+#[cfg(unix)]
+const KILL_SIGNAL: i32 = libc::SIGKILL;
+#[cfg(not(unix))]
+const KILL_SIGNAL: i32 = 9;
+
+/// Signal received by `railsup dev` that should stop everything (0 = none)
+static RECEIVED_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// First delay before restarting a process; doubles on each restart
+const RESTART_BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+
+/// Longest delay between restarts
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// A process that stays up this long starts its restart count over
+const RESTART_RESET_AFTER: Duration = Duration::from_secs(60);
+
+/// Restart limit for `on-failure` when railsup.toml doesn't set one
+const DEFAULT_MAX_RETRIES: u32 = 5;
 
 /// Process colors for output prefixes (only used when stdout is a TTY)
 const COLORS: &[&str] = &[
@@ -60,7 +91,7 @@ fn get_reset() -> &'static str {
     }
 }
 
-pub fn run(port: u16) -> Result<()> {
+pub fn run(port: Option<u16>, keep_alive: bool, no_dotenv: bool) -> Result<()> {
     // 1. Detect bundle context (finds Rails root + Gemfile)
     let current_dir = env::current_dir()?;
     let bundle_ctx = detect_bundle_context(&current_dir).ok_or_else(|| {
@@ -75,6 +106,18 @@ pub fn run(port: u16) -> Result<()> {
     // 2. Ensure Ruby is available (auto-bootstrap if needed)
     let ruby_version = ensure_ruby_available()?;
     let ruby_bin = paths::ruby_bin_dir(&ruby_version);
+    usage::record(&bundle_ctx.rails_root, &ruby_version);
+
+    // Load .env files from the Rails root (real env vars still win)
+    let dotenv_vars = if no_dotenv {
+        vec![]
+    } else {
+        dotenv::load_dotenv(&bundle_ctx.rails_root)?
+    };
+    let port = match port {
+        Some(port) => port,
+        None => base_port(&dotenv_vars)?,
+    };
 
     // 3. Check for bundler version mismatch (PEP-0016)
     if let Some(warning) = check_bundler_version_mismatch(&bundle_ctx, &ruby_bin) {
@@ -84,21 +127,50 @@ pub fn run(port: u16) -> Result<()> {
     // 4. Check for missing Gemfile.lock and auto-install if needed
     if needs_bundle_install(&bundle_ctx) {
         ui::info("No Gemfile.lock found. Running bundle install...");
-        run_bundle_install(&bundle_ctx, &ruby_version)?;
+        run_bundle_install(&bundle_ctx, &ruby_version, &dotenv_vars)?;
     }
 
     // 5. Check for Procfile.dev
     let procfile_path = bundle_ctx.rails_root.join("Procfile.dev");
     if procfile_path.exists() {
-        run_with_procfile(&procfile_path, &bundle_ctx, &ruby_version, port)
+        run_with_procfile(
+            &procfile_path,
+            &bundle_ctx,
+            &ruby_version,
+            &dotenv_vars,
+            port,
+            keep_alive,
+        )
     } else {
-        run_server_only(&bundle_ctx, &ruby_bin, port)
+        run_server_only(&bundle_ctx, &ruby_bin, &dotenv_vars, port)
+    }
+}
+
+/// Port from `$PORT` (real env first, then .env files), else the default
+fn base_port(dotenv_vars: &[DotenvVar]) -> Result<u16> {
+    let value = env::var("PORT").ok().or_else(|| {
+        dotenv_vars
+            .iter()
+            .find(|v| v.key == "PORT" && v.applies())
+            .map(|v| v.value.clone())
+    });
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(DEFAULT_PORT),
+        Some(value) => match value.parse() {
+            Ok(port) => Ok(port),
+            Err(_) => bail!("Invalid PORT '{}' (expected a port number)", value),
+        },
     }
 }
 
 /// Run bundle install to create Gemfile.lock
-fn run_bundle_install(bundle_ctx: &BundleContext, ruby_version: &str) -> Result<()> {
-    let env_vars = build_full_env(ruby_version, &Some(bundle_ctx.clone()));
+fn run_bundle_install(
+    bundle_ctx: &BundleContext,
+    ruby_version: &str,
+    dotenv_vars: &[DotenvVar],
+) -> Result<()> {
+    let mut env_vars = build_full_env(ruby_version, &Some(bundle_ctx.clone()));
+    dotenv::apply(&mut env_vars, dotenv_vars);
     let ruby_bin = paths::ruby_bin_dir(ruby_version);
     let bundle_path = ruby_bin.join("bundle");
 
@@ -123,11 +195,21 @@ fn run_bundle_install(bundle_ctx: &BundleContext, ruby_version: &str) -> Result<
 }
 
 /// Run all processes defined in Procfile.dev
+///
+/// Like foreman, the first process to exit stops all the others, and
+/// `railsup dev` exits with its status. With `keep_alive` the rest keep
+/// running instead.
+/// Processes with a restart policy in railsup.toml are restarted first.
+///
+/// Ports follow foreman: every process gets `$PORT`, `port + 100 * index`
+/// by its position in the Procfile.
 fn run_with_procfile(
     procfile_path: &Path,
     bundle_ctx: &BundleContext,
     ruby_version: &str,
+    dotenv_vars: &[DotenvVar],
     port: u16,
+    keep_alive: bool,
 ) -> Result<()> {
     let processes = parse_procfile(procfile_path)?;
 
@@ -135,18 +217,62 @@ fn run_with_procfile(
         bail!("Procfile.dev is empty");
     }
 
+    let dev_config = ProjectConfig::load_from_dir(&bundle_ctx.rails_root)?
+        .map(|config| config.dev)
+        .unwrap_or_default();
+    let restart_policies = dev_config.restart;
+    let web = dev_config.web.as_deref().unwrap_or(DEFAULT_WEB_PROCESS);
+    if dev_config.web.is_some() && !processes.iter().any(|(n, _)| n == web) {
+        ui::warn(&format!(
+            "railsup.toml sets web = '{}', which is not in Procfile.dev",
+            web
+        ));
+    }
+    for name in restart_policies.keys() {
+        if !processes.iter().any(|(n, _)| n == name) {
+            ui::warn(&format!(
+                "railsup.toml sets a restart policy for '{}', which is not in Procfile.dev",
+                name
+            ));
+        }
+    }
+    for name in &dev_config.interactive {
+        if !processes.iter().any(|(n, _)| n == name) {
+            ui::warn(&format!(
+                "railsup.toml marks '{}' as interactive, which is not in Procfile.dev",
+                name
+            ));
+        }
+    }
+
+    let names: Vec<&str> = processes.iter().map(|(n, _)| n.as_str()).collect();
+    let process_ports = assign_ports(names.len(), port)?;
+    for process_port in &process_ports {
+        ports::ensure_free(*process_port)?;
+    }
+
     ui::info("Starting development processes...");
 
     // Build environment with full Ruby + bundle context (PEP-0016)
-    let env_vars = build_full_env(ruby_version, &Some(bundle_ctx.clone()));
+    let mut env_vars = build_full_env(ruby_version, &Some(bundle_ctx.clone()));
+    dotenv::apply(&mut env_vars, dotenv_vars);
+
+    // Handle signals before the first spawn: the processes get their own
+    // groups, so a Ctrl+C during startup would otherwise kill only railsup
+    if let Err(e) = install_signal_handlers() {
+        ui::warn(&format!("Could not set signal handler: {}", e));
+    }
 
     // Spawn all processes
-    let mut children: Vec<(String, Child)> = vec![];
+    let mut managed: Vec<ManagedProcess> = vec![];
     let bundle_ctx_opt = Some(bundle_ctx.clone());
     for (i, (name, mut command)) in processes.into_iter().enumerate() {
-        // Replace port in web process
-        if name == "web" {
-            command = replace_port_in_command(&command, port);
+        let process_port = process_ports[i];
+        command = expand_port(&command, process_port);
+
+        // Point an explicit -p/--port in the web process at its port too
+        if name == web {
+            command = replace_port_in_command(&command, process_port);
         }
 
         // Wrap Procfile commands with bundle exec if needed (PEP-0016)
@@ -156,135 +282,414 @@ fn run_with_procfile(
         let reset = get_reset();
         ui::info(&format!("{}[{}]{} {}", color, name, reset, command));
 
-        let child = spawn_process(&command, &bundle_ctx.rails_root, &env_vars)?;
-        children.push((name, child));
-    }
-
-    // Set up signal handling for graceful shutdown
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-    if let Err(e) = ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-    }) {
-        ui::warn(&format!("Could not set signal handler: {}", e));
+        let mut env = env_vars.clone();
+        env.insert("PORT".into(), process_port.to_string());
+
+        let restart = restart_policies.get(&name).copied().unwrap_or_default();
+        let interactive = dev_config.interactive.contains(&name);
+        let spawned = ManagedProcess::spawn(ProcessSpec {
+            prefix: format!("{}[{}]{}", color, name, reset),
+            name,
+            command,
+            working_dir: bundle_ctx.rails_root.clone(),
+            env,
+            restart,
+            interactive,
+        });
+        match spawned {
+            Ok(process) => managed.push(process),
+            Err(e) => {
+                graceful_shutdown(&mut managed, SHUTDOWN_SIGNAL);
+                return Err(e);
+            }
+        }
     }
 
     println!();
 
-    // Determine if we should use colors (check once, pass to threads)
-    let colors_enabled = use_colors();
+    let outcome = supervise(&mut managed, &RECEIVED_SIGNAL, keep_alive);
+
+    // Wait for all output threads to finish
+    for process in &mut managed {
+        process.join_output();
+    }
 
-    // Stream output from all processes
-    let handles: Vec<_> = children
-        .into_iter()
-        .enumerate()
-        .map(|(i, (name, mut child))| {
-            let color = if colors_enabled {
-                COLORS[i % COLORS.len()].to_string()
+    match outcome {
+        Outcome::Interrupted => Ok(()),
+        Outcome::ProcessExited { name, status } => {
+            if !status.success() {
+                ui::error(&format!(
+                    "{} exited with {}",
+                    name,
+                    describe_status(&status)
+                ));
+            }
+            std::process::exit(exit_code(&status))
+        }
+        Outcome::AllExited => {
+            let failed: Vec<String> = managed
+                .iter()
+                .filter_map(|p| {
+                    let status = p.status?;
+                    (!status.success())
+                        .then(|| format!("{} ({})", p.spec.name, describe_status(&status)))
+                })
+                .collect();
+            if failed.is_empty() {
+                Ok(())
             } else {
-                String::new()
-            };
-            let reset = if colors_enabled {
-                RESET.to_string()
+                bail!("Processes failed: {}", failed.join(", "))
+            }
+        }
+    }
+}
+
+/// Everything needed to (re)start one Procfile process
+struct ProcessSpec {
+    name: String,
+    /// Colored `[name]` used for its output and log lines
+    prefix: String,
+    command: String,
+    working_dir: PathBuf,
+    env: HashMap<String, String>,
+    restart: RestartConfig,
+    /// Keeps the terminal's stdin and stays in railsup's process group, so
+    /// only its shell is signalled
+    interactive: bool,
+}
+
+/// A Procfile process and the threads relaying its output
+struct ManagedProcess {
+    spec: ProcessSpec,
+    child: Child,
+    started: Instant,
+    /// Set once the process has exited
+    status: Option<ExitStatus>,
+    output: Vec<thread::JoinHandle<()>>,
+    /// Restarts since the process last ran long enough to count as healthy
+    restarts: u32,
+    /// When a scheduled restart is due
+    restart_at: Option<Instant>,
+    /// Cleared once the group is seen empty after its shell was reaped;
+    /// from then on its id may belong to an unrelated group
+    group_alive: bool,
+}
+
+impl ManagedProcess {
+    /// Start a process and relay its output with its prefix
+    fn spawn(spec: ProcessSpec) -> Result<Self> {
+        let child = spawn_process(&spec)?;
+        let mut process = Self {
+            spec,
+            child,
+            started: Instant::now(),
+            status: None,
+            output: vec![],
+            restarts: 0,
+            restart_at: None,
+            group_alive: true,
+        };
+        process.relay_output();
+        Ok(process)
+    }
+
+    fn relay_output(&mut self) {
+        if let Some(out) = self.child.stdout.take() {
+            let prefix = self.spec.prefix.clone();
+            self.output.push(relay_lines(out, prefix, false));
+        }
+        if let Some(err) = self.child.stderr.take() {
+            let prefix = self.spec.prefix.clone();
+            self.output.push(relay_lines(err, prefix, true));
+        }
+    }
+
+    /// Check whether the process has exited, recording its status
+    fn poll(&mut self) -> bool {
+        if self.status.is_none() {
+            match self.child.try_wait() {
+                Ok(Some(status)) => self.status = Some(status),
+                Ok(None) => return false,
+                // Treat errors as done
+                Err(_) => self.status = Some(ExitStatus::default()),
+            }
+            // Check right away, while the group id can't have been reused
+            self.group_running();
+        }
+        true
+    }
+
+    /// Whether the process group may still have something running
+    ///
+    /// While the shell hasn't been reaped its pid keeps the group id taken.
+    /// After that an empty group is remembered as dead and never signalled
+    /// again, since the id may be reused.
+    fn group_running(&mut self) -> bool {
+        if self.group_alive && self.status.is_some() {
+            self.group_alive = !self.spec.interactive && group_alive(&self.child);
+        }
+        self.group_alive
+    }
+
+    /// Send `signal` to the process group, unless it is known to be gone
+    fn signal(&mut self, signal: i32) {
+        if !self.group_running() {
+            return;
+        }
+        if self.spec.interactive {
+            signal_process(&mut self.child, signal);
+        } else {
+            signal_group(&mut self.child, signal);
+        }
+    }
+
+    /// Start the same command again after it exited
+    ///
+    /// If the command can't be spawned, the attempt is recorded as an
+    /// immediate failed exit so it counts against the restart policy.
+    fn restart(&mut self) -> Result<()> {
+        // Anything the old shell left behind would hold on to ports
+        self.signal(KILL_SIGNAL);
+        self.join_output();
+        self.restart_at = None;
+        self.started = Instant::now();
+        match spawn_process(&self.spec) {
+            Ok(child) => {
+                self.child = child;
+                self.status = None;
+                self.group_alive = true;
+                self.relay_output();
+                Ok(())
+            }
+            Err(e) => {
+                self.status = Some(spawn_failed_status());
+                Err(e)
+            }
+        }
+    }
+
+    fn join_output(&mut self) {
+        for handle in self.output.drain(..) {
+            handle.join().ok();
+        }
+    }
+}
+
+/// Print each line from `reader` with `prefix` on stdout or stderr
+fn relay_lines<R: Read + Send + 'static>(
+    reader: R,
+    prefix: String,
+    to_stderr: bool,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if to_stderr {
+                eprintln!("{} {}", prefix, line);
             } else {
-                String::new()
-            };
+                println!("{} {}", prefix, line);
+            }
+        }
+    })
+}
 
-            // Take stdout and stderr
-            let stdout = child.stdout.take();
-            let stderr = child.stderr.take();
-
-            let name_clone = name.clone();
-            let color_clone = color.clone();
-            let reset_clone = reset.clone();
-
-            // Spawn thread to read stdout
-            let stdout_handle = stdout.map(|out| {
-                let name = name_clone.clone();
-                let color = color_clone.clone();
-                let reset = reset_clone.clone();
-                thread::spawn(move || {
-                    let reader = BufReader::new(out);
-                    for line in reader.lines().map_while(Result::ok) {
-                        println!("{}[{}]{} {}", color, name, reset, line);
-                    }
-                })
-            });
-
-            // Spawn thread to read stderr
-            let stderr_handle = stderr.map(|err| {
-                let name = name.clone();
-                let color = color.clone();
-                let reset = reset.clone();
-                thread::spawn(move || {
-                    let reader = BufReader::new(err);
-                    for line in reader.lines().map_while(Result::ok) {
-                        eprintln!("{}[{}]{} {}", color, name, reset, line);
-                    }
-                })
-            });
+/// How long to wait before restarting a process that exited, or None if
+/// its policy says to leave it stopped
+///
+/// `restarts` counts previous restarts; the delay doubles each time.
+fn restart_delay(config: &RestartConfig, success: bool, restarts: u32) -> Option<Duration> {
+    let max_retries = match config.policy {
+        RestartPolicy::Never => return None,
+        RestartPolicy::OnFailure if success => return None,
+        RestartPolicy::OnFailure => Some(config.max_retries.unwrap_or(DEFAULT_MAX_RETRIES)),
+        RestartPolicy::Always => config.max_retries,
+    };
+    if max_retries.is_some_and(|max| restarts >= max) {
+        return None;
+    }
 
-            (child, stdout_handle, stderr_handle)
-        })
-        .collect();
+    let delay = RESTART_BACKOFF_INITIAL.saturating_mul(1 << restarts.min(16));
+    Some(delay.min(RESTART_BACKOFF_MAX))
+}
 
-    // Wait for processes or signal - graceful shutdown on Ctrl+C
-    let mut children_to_wait: Vec<_> = handles;
+/// How a supervised run ended
+#[derive(Debug)]
+enum Outcome {
+    /// Ctrl+C or another stop signal, forwarded to every process
+    Interrupted,
+    /// A process exited and the others were stopped
+    ProcessExited { name: String, status: ExitStatus },
+    /// Every process exited on its own (only with keep-alive)
+    AllExited,
+}
 
+/// Wait for a signal or for processes to exit, restarting those whose
+/// policy allows it
+///
+/// `received` holds the last stop signal railsup got (0 for none); it is
+/// forwarded to every process group.
+fn supervise(processes: &mut [ManagedProcess], received: &AtomicI32, keep_alive: bool) -> Outcome {
     loop {
-        if !running.load(Ordering::SeqCst) {
-            // Ctrl+C received - graceful shutdown
-            graceful_shutdown(&mut children_to_wait);
-            break;
+        let signal = received.load(Ordering::SeqCst);
+        if signal != 0 {
+            graceful_shutdown(processes, signal);
+            return Outcome::Interrupted;
         }
 
-        // Check if all processes have exited
         let mut all_done = true;
-        for (child, _, _) in &mut children_to_wait {
-            match child.try_wait() {
-                Ok(Some(_)) => {} // This one is done
-                Ok(None) => all_done = false,
-                Err(_) => {} // Treat errors as done
+        for i in 0..processes.len() {
+            let process = &mut processes[i];
+
+            if let Some(due) = process.restart_at {
+                if Instant::now() < due {
+                    process.group_running();
+                    all_done = false;
+                    continue;
+                }
+                // A failed restart is handled like the process exiting again
+                match process.restart() {
+                    Ok(()) => {
+                        all_done = false;
+                        continue;
+                    }
+                    Err(e) => {
+                        ui::warn(&format!("{} failed to restart: {}", process.spec.prefix, e));
+                    }
+                }
+            } else {
+                let already_exited = process.status.is_some();
+                if !process.poll() {
+                    all_done = false;
+                    continue;
+                }
+                if already_exited {
+                    process.group_running();
+                    continue;
+                }
+            }
+
+            let status = process.status.unwrap_or_default();
+            if process.started.elapsed() >= RESTART_RESET_AFTER {
+                process.restarts = 0;
+            }
+            if let Some(delay) =
+                restart_delay(&process.spec.restart, status.success(), process.restarts)
+            {
+                process.restarts += 1;
+                process.restart_at = Some(Instant::now() + delay);
+                println!(
+                    "{} exited with {}, restarting in {}s (restart {})",
+                    process.spec.prefix,
+                    describe_status(&status),
+                    delay.as_secs_f32(),
+                    process.restarts
+                );
+                all_done = false;
+                continue;
+            }
+
+            let name = process.spec.name.clone();
+            let gave_up = if process.restarts > 0 {
+                format!(" after {} restarts", process.restarts)
+            } else {
+                String::new()
+            };
+            if keep_alive {
+                ui::warn(&format!(
+                    "{} exited with {}{}",
+                    name,
+                    describe_status(&status),
+                    gave_up
+                ));
+            } else {
+                println!();
+                let banner = format!(
+                    "{} exited with {}{}, stopping all processes",
+                    name,
+                    describe_status(&status),
+                    gave_up
+                );
+                if status.success() {
+                    ui::info(&banner);
+                } else {
+                    ui::error(&banner);
+                }
+                graceful_shutdown(processes, SHUTDOWN_SIGNAL);
+                return Outcome::ProcessExited { name, status };
             }
         }
 
         if all_done {
-            break;
+            return Outcome::AllExited;
         }
 
         thread::sleep(Duration::from_millis(100));
     }
+}
 
-    // Wait for all output threads to finish
-    for (_, stdout_handle, stderr_handle) in children_to_wait {
-        if let Some(h) = stdout_handle {
-            h.join().ok();
-        }
-        if let Some(h) = stderr_handle {
-            h.join().ok();
+/// Status recorded for a process that couldn't be spawned: code 127, as a
+/// shell reports a command it can't run
+fn spawn_failed_status() -> ExitStatus {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw(127 << 8)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::ExitStatusExt;
+        ExitStatus::from_raw(127)
+    }
+}
+
+/// Exit code that passes on a process's status (128 + signal, as shells
+/// report a process killed by a signal)
+fn exit_code(status: &ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
         }
     }
+    1
+}
 
-    Ok(())
+/// Describe an exit status for banners ("code 1", "signal 9")
+fn describe_status(status: &ExitStatus) -> String {
+    if let Some(code) = status.code() {
+        return format!("code {}", code);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return format!("signal {}", signal);
+        }
+    }
+    "unknown status".to_string()
 }
 
 /// Gracefully shutdown all child processes
-/// Sends SIGTERM first, waits for timeout, then SIGKILL if needed
-fn graceful_shutdown(children: &mut [ChildWithHandles]) {
-    // First, send SIGTERM to all children (Unix) or kill (Windows)
-    for (child, _, _) in children.iter_mut() {
-        terminate_process(child);
+///
+/// Sends `signal` to every process group, waits for the groups to empty,
+/// then SIGKILLs whatever is left after `SHUTDOWN_TIMEOUT`. Groups whose
+/// shell already exited are signalled too, since their children may not
+/// have, unless they were already seen empty.
+fn graceful_shutdown(processes: &mut [ManagedProcess], signal: i32) {
+    for process in processes.iter_mut() {
+        process.signal(signal);
     }
 
     // Wait for processes to exit gracefully
     let start = Instant::now();
     loop {
         let mut all_done = true;
-        for (child, _, _) in children.iter_mut() {
-            match child.try_wait() {
-                Ok(Some(_)) => {} // Done
-                Ok(None) => all_done = false,
-                Err(_) => {} // Treat errors as done
+        for process in processes.iter_mut() {
+            if !process.poll() || process.group_running() {
+                all_done = false;
             }
         }
 
@@ -294,8 +699,11 @@ fn graceful_shutdown(children: &mut [ChildWithHandles]) {
 
         if start.elapsed() >= SHUTDOWN_TIMEOUT {
             // Timeout - force kill remaining processes
-            for (child, _, _) in children.iter_mut() {
-                child.kill().ok();
+            for process in processes.iter_mut() {
+                process.signal(KILL_SIGNAL);
+                if process.status.is_none() {
+                    process.status = process.child.wait().ok();
+                }
             }
             return;
         }
@@ -304,23 +712,81 @@ fn graceful_shutdown(children: &mut [ChildWithHandles]) {
     }
 }
 
-/// Send SIGTERM to a process (Unix) or kill it (Windows)
+/// Stop signals railsup forwards to its processes
+#[cfg(unix)]
+const FORWARDED_SIGNALS: [i32; 4] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP, libc::SIGQUIT];
+
+#[cfg(unix)]
+extern "C" fn record_signal(signal: libc::c_int) {
+    // Only async-signal-safe work here; the supervise loop does the rest
+    RECEIVED_SIGNAL.store(signal, Ordering::SeqCst);
+}
+
+/// Record SIGINT, SIGTERM, SIGHUP and SIGQUIT in `RECEIVED_SIGNAL`
+#[cfg(unix)]
+fn install_signal_handlers() -> std::io::Result<()> {
+    for signal in FORWARDED_SIGNALS {
+        let handler = record_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        if unsafe { libc::signal(signal, handler) } == libc::SIG_ERR {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn install_signal_handlers() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| RECEIVED_SIGNAL.store(2, Ordering::SeqCst))
+}
+
+/// Send `signal` to a process's group: its shell and everything it started
+#[cfg(unix)]
+fn signal_group(child: &mut Child, signal: i32) {
+    // Each process leads its own group (see spawn_process)
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, signal);
+    }
+}
+
+/// Send `signal` to a process's shell only (interactive processes share
+/// railsup's group)
 #[cfg(unix)]
-fn terminate_process(child: &Child) {
-    // Send SIGTERM to the process for graceful shutdown
+fn signal_process(child: &mut Child, signal: i32) {
     unsafe {
-        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+        libc::kill(child.id() as libc::pid_t, signal);
     }
 }
 
+/// Whether anything in a process's group is still running
+#[cfg(unix)]
+fn group_alive(child: &Child) -> bool {
+    unsafe { libc::killpg(child.id() as libc::pid_t, 0) == 0 }
+}
+
 #[cfg(not(unix))]
-fn terminate_process(child: &mut Child) {
+fn signal_group(child: &mut Child, _signal: i32) {
     // On non-Unix, just kill immediately
     child.kill().ok();
 }
 
+#[cfg(not(unix))]
+fn signal_process(child: &mut Child, _signal: i32) {
+    child.kill().ok();
+}
+
+#[cfg(not(unix))]
+fn group_alive(_child: &Child) -> bool {
+    false
+}
+
 /// Run Rails server only (fallback when no Procfile.dev)
-fn run_server_only(bundle_ctx: &BundleContext, ruby_bin: &Path, port: u16) -> Result<()> {
+fn run_server_only(
+    bundle_ctx: &BundleContext,
+    ruby_bin: &Path,
+    dotenv_vars: &[DotenvVar],
+    port: u16,
+) -> Result<()> {
+    ports::ensure_free(port)?;
     ui::info(&format!("Starting Rails on http://localhost:{}", port));
 
     let port_str = port.to_string();
@@ -348,7 +814,9 @@ fn run_server_only(bundle_ctx: &BundleContext, ruby_bin: &Path, port: u16) -> Re
         .and_then(|n| n.to_str())
         .map(|s| s.trim_start_matches("ruby-"))
         .unwrap_or("unknown");
-    let env_vars = build_full_env(ruby_version, &bundle_ctx_opt);
+    let mut env_vars = build_full_env(ruby_version, &bundle_ctx_opt);
+    dotenv::apply(&mut env_vars, dotenv_vars);
+    env_vars.insert("PORT".into(), port.to_string());
 
     let status = Command::new(&cmd_path)
         .args(&args)
@@ -432,6 +900,51 @@ fn replace_port_in_command(command: &str, port: u16) -> String {
     command.to_string()
 }
 
+/// Ports for `count` Procfile processes: `base + 100 * index`, as foreman
+/// assigns them
+fn assign_ports(count: usize, base: u16) -> Result<Vec<u16>> {
+    (0..count as u32)
+        .map(
+            |index| match u16::try_from(u32::from(base) + index * u32::from(PORT_STEP)) {
+                Ok(port) => Ok(port),
+                Err(_) => bail!(
+                    "Port {} leaves no room for {} Procfile processes ({} apart)",
+                    base,
+                    count,
+                    PORT_STEP
+                ),
+            },
+        )
+        .collect()
+}
+
+/// Expand `$PORT` and `${PORT}` in a Procfile command, as foreman does
+fn expand_port(command: &str, port: u16) -> String {
+    let port = port.to_string();
+    let mut out = String::with_capacity(command.len());
+    let mut rest = command;
+
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        if let Some(tail) = after.strip_prefix("{PORT}") {
+            out.push_str(&port);
+            rest = tail;
+        } else if let Some(tail) = after
+            .strip_prefix("PORT")
+            .filter(|t| !t.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_'))
+        {
+            out.push_str(&port);
+            rest = tail;
+        } else {
+            out.push('$');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
 /// Try to replace port after a given pattern, returns None if pattern not found
 fn try_replace_port(
     command: &str,
@@ -466,22 +979,32 @@ fn try_replace_port(
     Some(result)
 }
 
-/// Spawn a process with the given command
-fn spawn_process(
-    command: &str,
-    working_dir: &Path,
-    env_vars: &HashMap<String, String>,
-) -> Result<Child> {
+/// Spawn a Procfile process
+fn spawn_process(spec: &ProcessSpec) -> Result<Child> {
     // Use shell to handle command parsing
-    let child = Command::new("sh")
-        .args(["-c", command])
-        .current_dir(working_dir)
-        .envs(env_vars)
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", &spec.command])
+        .current_dir(&spec.working_dir)
+        .envs(&spec.env)
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
+        .stderr(Stdio::piped());
+
+    // Give each process its own group so signals reach everything it
+    // starts (puma workers, node watchers), not just `sh`. Background
+    // groups can't read the terminal, hence the null stdin; interactive
+    // processes stay in the foreground group and keep it.
+    if spec.interactive {
+        cmd.stdin(Stdio::inherit());
+    } else {
+        cmd.stdin(Stdio::null());
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+    }
 
-    Ok(child)
+    Ok(cmd.spawn()?)
 }
 
 #[cfg(test)]
@@ -490,6 +1013,193 @@ mod tests {
     use crate::cli::bundler::find_rails_root;
     use tempfile::tempdir;
 
+    // ==================== supervise tests ====================
+
+    fn spawn_managed(name: &str, command: &str, restart: RestartConfig) -> ManagedProcess {
+        ManagedProcess::spawn(ProcessSpec {
+            name: name.to_string(),
+            prefix: format!("[{}]", name),
+            command: command.to_string(),
+            working_dir: std::env::temp_dir(),
+            env: HashMap::new(),
+            restart,
+            interactive: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn first_exit_stops_other_processes() {
+        let mut processes = vec![
+            spawn_managed("web", "sleep 30", RestartConfig::default()),
+            spawn_managed("css", "exit 3", RestartConfig::default()),
+        ];
+        let received = AtomicI32::new(0);
+
+        let start = Instant::now();
+        let outcome = supervise(&mut processes, &received, false);
+
+        match outcome {
+            Outcome::ProcessExited { name, status } => {
+                assert_eq!(name, "css");
+                assert_eq!(status.code(), Some(3));
+            }
+            other => panic!("expected ProcessExited, got {:?}", other),
+        }
+        assert!(processes.iter().all(|p| p.status.is_some()));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn exit_code_passes_on_status() {
+        use std::os::unix::process::ExitStatusExt;
+        assert_eq!(exit_code(&ExitStatus::from_raw(0)), 0);
+        assert_eq!(exit_code(&ExitStatus::from_raw(3 << 8)), 3);
+        assert_eq!(exit_code(&ExitStatus::from_raw(libc::SIGKILL)), 137);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn keep_alive_waits_for_every_process() {
+        let mut processes = vec![
+            spawn_managed("web", "sleep 0.3", RestartConfig::default()),
+            spawn_managed("css", "exit 1", RestartConfig::default()),
+        ];
+        let received = AtomicI32::new(0);
+
+        let outcome = supervise(&mut processes, &received, true);
+
+        assert!(matches!(outcome, Outcome::AllExited));
+        assert!(processes[0].status.unwrap().success());
+        assert_eq!(processes[1].status.unwrap().code(), Some(1));
+    }
+
+    /// Whether `pid` is a live (non-zombie) process
+    #[cfg(unix)]
+    fn process_running(pid: &str) -> bool {
+        let output = Command::new("ps")
+            .args(["-o", "stat=", "-p", pid])
+            .output()
+            .unwrap();
+        let stat = String::from_utf8_lossy(&output.stdout);
+        !stat.trim().is_empty() && !stat.trim().starts_with('Z')
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn shutdown_stops_grandchildren() {
+        let dir = tempdir().unwrap();
+        let pid_file = dir.path().join("sleep.pid");
+        let command = format!("sleep 300 & echo $! > {}; wait", pid_file.display());
+        let mut processes = vec![spawn_managed("web", &command, RestartConfig::default())];
+
+        let start = Instant::now();
+        while !pid_file.exists() && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(20));
+        }
+        thread::sleep(Duration::from_millis(50));
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let pid = pid.trim();
+        assert!(process_running(pid));
+
+        let received = AtomicI32::new(libc::SIGTERM);
+        let outcome = supervise(&mut processes, &received, false);
+
+        assert!(matches!(outcome, Outcome::Interrupted));
+        assert!(!process_running(pid), "sleep {} survived shutdown", pid);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn emptied_group_is_not_signalled_again() {
+        let mut processes = vec![spawn_managed("css", "exit 0", RestartConfig::default())];
+        let start = Instant::now();
+        while !processes[0].poll() && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        assert!(!processes[0].group_running());
+        assert!(!processes[0].group_alive);
+        graceful_shutdown(&mut processes, libc::SIGTERM);
+        assert!(processes[0].status.unwrap().success());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn on_failure_restarts_until_retries_run_out() {
+        let restart = RestartConfig {
+            policy: RestartPolicy::OnFailure,
+            max_retries: Some(1),
+        };
+        let mut processes = vec![spawn_managed("worker", "exit 2", restart)];
+        let received = AtomicI32::new(0);
+
+        let outcome = supervise(&mut processes, &received, false);
+
+        assert!(matches!(outcome, Outcome::ProcessExited { .. }));
+        assert_eq!(processes[0].restarts, 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failed_restart_counts_against_retries() {
+        let restart = RestartConfig {
+            policy: RestartPolicy::Always,
+            max_retries: Some(2),
+        };
+        let mut processes = vec![spawn_managed("worker", "exit 0", restart)];
+        // Restarts can't spawn once the working directory is gone
+        processes[0].spec.working_dir = std::env::temp_dir().join("railsup-missing-dir");
+        let received = AtomicI32::new(0);
+
+        let start = Instant::now();
+        let outcome = supervise(&mut processes, &received, false);
+
+        match outcome {
+            Outcome::ProcessExited { name, status } => {
+                assert_eq!(name, "worker");
+                assert_eq!(status.code(), Some(127));
+            }
+            other => panic!("expected ProcessExited, got {:?}", other),
+        }
+        assert_eq!(processes[0].restarts, 2);
+        assert!(processes[0].restart_at.is_none());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn restart_delay_follows_policy_and_backs_off() {
+        let never = RestartConfig::default();
+        assert_eq!(restart_delay(&never, false, 0), None);
+
+        let on_failure = RestartConfig {
+            policy: RestartPolicy::OnFailure,
+            max_retries: None,
+        };
+        assert_eq!(restart_delay(&on_failure, true, 0), None);
+        assert_eq!(
+            restart_delay(&on_failure, false, 0),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            restart_delay(&on_failure, false, 2),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(restart_delay(&on_failure, false, DEFAULT_MAX_RETRIES), None);
+
+        let always = RestartConfig {
+            policy: RestartPolicy::Always,
+            max_retries: None,
+        };
+        assert_eq!(
+            restart_delay(&always, true, 0),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(restart_delay(&always, false, 40), Some(RESTART_BACKOFF_MAX));
+    }
+
     // ==================== find_rails_root tests ====================
     // (Tests now use bundler::find_rails_root)
 
@@ -640,6 +1350,28 @@ mod tests {
         );
     }
 
+    // ==================== PORT allocation tests ====================
+
+    #[test]
+    fn assign_ports_follows_procfile_order() {
+        assert_eq!(assign_ports(3, 3000).unwrap(), vec![3000, 3100, 3200]);
+        assert_eq!(assign_ports(2, 5000).unwrap(), vec![5000, 5100]);
+        assert!(assign_ports(2, 65500).is_err());
+    }
+
+    #[test]
+    fn expand_port_in_command() {
+        assert_eq!(
+            expand_port("bin/rails server -p $PORT", 3000),
+            "bin/rails server -p 3000"
+        );
+        assert_eq!(
+            expand_port("vite --port=${PORT} --host $HOST", 3100),
+            "vite --port=3100 --host $HOST"
+        );
+        assert_eq!(expand_port("echo $PORTS $ $", 3000), "echo $PORTS $ $");
+    }
+
     // ==================== replace_port_in_command tests ====================
 
     #[test]
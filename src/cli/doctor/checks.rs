@@ -1,11 +1,12 @@
 //! Diagnostic checks for the doctor command
 
 use super::report::*;
-use crate::{config::Config, paths};
+use crate::ruby::{self, external};
+use crate::{build, config::Config, paths};
 use anyhow::Result;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Collect all diagnostics into a report
 pub fn collect_diagnostics() -> Result<DiagnosticReport> {
@@ -24,6 +25,7 @@ pub fn collect_diagnostics() -> Result<DiagnosticReport> {
         conflicts,
         path_analysis,
         environment: check_environment(),
+        build_dependencies: check_build_dependencies(),
         project: analyze_project(),
     })
 }
@@ -55,13 +57,13 @@ fn list_ruby_versions() -> Result<Vec<RubyVersionInfo>> {
         return Ok(vec![]);
     }
 
-    let config = Config::load()?;
-    let default_version = config.default_ruby().map(|s| s.to_string());
+    let default_version = resolved_default(&Config::load()?);
 
     let mut versions = vec![];
     for entry in fs::read_dir(ruby_dir)? {
         let entry = entry?;
-        if entry.file_type()?.is_dir() {
+        // Follow symlinks so linked Rubies are listed too
+        if entry.path().is_dir() {
             let name = entry.file_name().to_string_lossy().to_string();
             if name.starts_with('.') {
                 continue;
@@ -83,16 +85,20 @@ fn list_ruby_versions() -> Result<Vec<RubyVersionInfo>> {
     Ok(versions)
 }
 
+/// The default Ruby as an installed version (aliases and channels resolved)
+fn resolved_default(config: &Config) -> Option<String> {
+    let name = config.default_ruby()?;
+    ruby::resolve_installed(config, name).ok()
+}
+
 /// Get Ruby installation status summary
 fn get_ruby_status(versions: &[RubyVersionInfo]) -> Result<RubyStatus> {
-    let default_version = Config::load()
-        .ok()
-        .and_then(|c| c.default_ruby().map(|s| s.to_string()));
+    let config = Config::load().ok();
 
     Ok(RubyStatus {
         any_installed: !versions.is_empty(),
-        default_set: default_version.is_some(),
-        default_version,
+        default_set: config.as_ref().is_some_and(|c| c.default_ruby().is_some()),
+        default_version: config.as_ref().and_then(resolved_default),
         installed_count: versions.len(),
     })
 }
@@ -268,7 +274,6 @@ fn check_file_for_shell_init(path: &PathBuf) -> Option<ShellIntegrationStatus> {
 
 /// Detect version manager conflicts
 fn detect_conflicts(shell_integration: &ShellIntegrationStatus) -> Vec<Conflict> {
-    let home = dirs::home_dir().unwrap_or_default();
     let path_env = env::var("PATH").unwrap_or_default();
     let path_entries: Vec<&str> = path_env.split(':').collect();
 
@@ -279,106 +284,46 @@ fn detect_conflicts(shell_integration: &ShellIntegrationStatus) -> Vec<Conflict>
 
     let mut conflicts = vec![];
 
-    // Check rbenv
-    let rbenv_dir = home.join(".rbenv");
-    let rbenv_exists = rbenv_dir.exists();
-    let rbenv_in_path = path_entries.iter().position(|p| p.contains(".rbenv/shims"));
-    conflicts.push(Conflict {
-        tool: "rbenv".to_string(),
-        detected: rbenv_exists,
-        location: if rbenv_exists { Some(rbenv_dir) } else { None },
-        in_path: rbenv_in_path.is_some(),
-        path_position: rbenv_in_path,
-        impact: if !rbenv_exists {
-            ConflictImpact::None
-        } else if railsup_active {
-            ConflictImpact::Overridden
-        } else if rbenv_in_path.is_some() {
-            ConflictImpact::Blocking
-        } else {
-            ConflictImpact::None
-        },
-    });
-
-    // Check asdf
-    let asdf_dir = home.join(".asdf");
-    let asdf_exists = asdf_dir.exists();
-    let asdf_in_path = path_entries.iter().position(|p| p.contains(".asdf/shims"));
-    conflicts.push(Conflict {
-        tool: "asdf".to_string(),
-        detected: asdf_exists,
-        location: if asdf_exists { Some(asdf_dir) } else { None },
-        in_path: asdf_in_path.is_some(),
-        path_position: asdf_in_path,
-        impact: if !asdf_exists {
-            ConflictImpact::None
-        } else if railsup_active {
-            ConflictImpact::Overridden
-        } else if asdf_in_path.is_some() {
-            ConflictImpact::Blocking
-        } else {
-            ConflictImpact::None
-        },
-    });
-
-    // Check rvm
-    let rvm_dir = home.join(".rvm");
-    let rvm_exists = rvm_dir.exists();
-    let rvm_in_path = path_entries.iter().position(|p| p.contains(".rvm"));
-    conflicts.push(Conflict {
-        tool: "rvm".to_string(),
-        detected: rvm_exists,
-        location: if rvm_exists { Some(rvm_dir) } else { None },
-        in_path: rvm_in_path.is_some(),
-        path_position: rvm_in_path,
-        impact: if !rvm_exists {
-            ConflictImpact::None
-        } else if railsup_active {
-            ConflictImpact::Overridden
-        } else if rvm_in_path.is_some() {
-            ConflictImpact::Blocking
-        } else {
-            ConflictImpact::None
-        },
-    });
-
-    // Check mise
-    let mise_dir = home.join(".local/share/mise");
-    let mise_exists = mise_dir.exists();
-    let mise_in_path = path_entries.iter().position(|p| p.contains("mise/shims"));
-    conflicts.push(Conflict {
-        tool: "mise".to_string(),
-        detected: mise_exists,
-        location: if mise_exists { Some(mise_dir) } else { None },
-        in_path: mise_in_path.is_some(),
-        path_position: mise_in_path,
-        impact: if !mise_exists {
-            ConflictImpact::None
-        } else if railsup_active {
-            ConflictImpact::Overridden
-        } else if mise_in_path.is_some() {
-            ConflictImpact::Blocking
-        } else {
-            ConflictImpact::None
-        },
-    });
+    for manager in external::version_managers() {
+        let exists = manager.root.exists();
+        let in_path = path_entries
+            .iter()
+            .position(|p| p.contains(manager.path_marker));
+        conflicts.push(Conflict {
+            tool: manager.name.to_string(),
+            detected: exists,
+            location: if exists { Some(manager.root) } else { None },
+            in_path: in_path.is_some(),
+            path_position: in_path,
+            impact: if !exists {
+                ConflictImpact::None
+            } else if railsup_active {
+                ConflictImpact::Overridden
+            } else if in_path.is_some() {
+                ConflictImpact::Blocking
+            } else {
+                ConflictImpact::None
+            },
+        });
+    }
 
     // Check Homebrew Ruby (macOS)
     #[cfg(target_os = "macos")]
     {
-        let homebrew_ruby = PathBuf::from("/opt/homebrew/opt/ruby");
-        let homebrew_in_path = path_entries
+        let homebrew_ruby = external::HOMEBREW_RUBY_PREFIXES
             .iter()
-            .position(|p| p.contains("/opt/homebrew/opt/ruby"));
-        if homebrew_ruby.exists() || homebrew_in_path.is_some() {
+            .map(PathBuf::from)
+            .find(|prefix| prefix.exists());
+        let homebrew_in_path = path_entries.iter().position(|p| {
+            external::HOMEBREW_RUBY_PREFIXES
+                .iter()
+                .any(|prefix| p.contains(prefix))
+        });
+        if homebrew_ruby.is_some() || homebrew_in_path.is_some() {
             conflicts.push(Conflict {
                 tool: "Homebrew Ruby".to_string(),
-                detected: homebrew_ruby.exists(),
-                location: if homebrew_ruby.exists() {
-                    Some(homebrew_ruby)
-                } else {
-                    None
-                },
+                detected: homebrew_ruby.is_some(),
+                location: homebrew_ruby,
                 in_path: homebrew_in_path.is_some(),
                 path_position: homebrew_in_path,
                 impact: if railsup_active {
@@ -420,18 +365,18 @@ fn analyze_path(ruby_status: &RubyStatus) -> PathAnalysis {
     let expected_ruby = if let Some(ref version) = ruby_status.default_version {
         paths::ruby_bin_dir(version).join("ruby")
     } else {
-        PathBuf::from("~/.railsup/ruby/ruby-VERSION/bin/ruby")
+        paths::ruby_version_dir("VERSION").join("bin/ruby")
     };
 
     // Check if ruby is correct
     let ruby_correct = which_ruby
         .as_ref()
-        .is_some_and(|p| p.to_string_lossy().contains(".railsup/ruby/"));
+        .is_some_and(|p| p.starts_with(paths::ruby_dir()));
 
     // Check if gem_bin is in PATH
     let gem_bin_in_path = entries
         .iter()
-        .any(|e| e.path.to_string_lossy().contains(".railsup/gems/"));
+        .any(|e| e.path.starts_with(paths::gems_dir()));
 
     PathAnalysis {
         entries,
@@ -446,9 +391,9 @@ fn analyze_path(ruby_status: &RubyStatus) -> PathAnalysis {
 
 /// Classify a PATH entry by its source
 fn classify_path_source(path: &str) -> PathSource {
-    if path.contains(".railsup/ruby/") {
+    if Path::new(path).starts_with(paths::ruby_dir()) {
         PathSource::Railsup
-    } else if path.contains(".railsup/gems/") {
+    } else if Path::new(path).starts_with(paths::gems_dir()) {
         PathSource::RailsupGems
     } else if path.contains(".rbenv") {
         PathSource::Rbenv
@@ -479,7 +424,7 @@ fn check_environment() -> EnvironmentCheck {
 
     // Check if GEM_HOME is set but not to railsup
     if let Some(ref gh) = gem_home {
-        if !gh.contains(".railsup/gems/") {
+        if !Path::new(gh).starts_with(paths::gems_dir()) {
             issues.push(format!("GEM_HOME={} (not railsup's)", gh));
         }
     }
@@ -504,6 +449,17 @@ fn check_environment() -> EnvironmentCheck {
     }
 }
 
+/// Check what compiling Ruby from source would need
+fn check_build_dependencies() -> Vec<BuildDependency> {
+    build::check_dependencies()
+        .into_iter()
+        .map(|dep| BuildDependency {
+            name: dep.name.to_string(),
+            found: dep.found,
+        })
+        .collect()
+}
+
 /// Analyze the current project (if in a Rails directory)
 fn analyze_project() -> Option<ProjectAnalysis> {
     let current_dir = env::current_dir().ok()?;
@@ -519,45 +475,27 @@ fn analyze_project() -> Option<ProjectAnalysis> {
     // Read .ruby-version
     let ruby_version_file = fs::read_to_string(current_dir.join(".ruby-version"))
         .ok()
-        .map(|s| s.trim().trim_start_matches("ruby-").to_string());
+        .and_then(|content| ruby::parse_ruby_version_file(&content));
 
     // Read railsup.toml
     let railsup_toml = fs::read_to_string(current_dir.join("railsup.toml"))
         .ok()
-        .and_then(|content| {
-            toml::from_str::<toml::Table>(&content)
-                .ok()
-                .and_then(|t| t.get("ruby").and_then(|v| v.as_str().map(String::from)))
-        });
+        .and_then(|content| ruby::parse_railsup_toml(&content));
 
-    // Read Gemfile ruby version (simple regex)
+    // Read Gemfile ruby version
     let gemfile_ruby = fs::read_to_string(current_dir.join("Gemfile"))
         .ok()
-        .and_then(|content| {
-            for line in content.lines() {
-                let trimmed = line.trim();
-                if trimmed.starts_with("ruby ") || trimmed.starts_with("ruby(") {
-                    // Extract version from ruby "3.3.0" or ruby("3.3.0")
-                    if let Some(start) = trimmed.find('"') {
-                        if let Some(end) = trimmed[start + 1..].find('"') {
-                            return Some(trimmed[start + 1..start + 1 + end].to_string());
-                        }
-                    }
-                }
-            }
-            None
-        });
+        .and_then(|content| ruby::parse_gemfile_ruby(&content, &current_dir));
 
-    // Check if versions match
-    let config = Config::load().ok();
-    let default_version = config.and_then(|c| c.default_ruby().map(|s| s.to_string()));
-    let project_version = railsup_toml
-        .as_ref()
-        .or(ruby_version_file.as_ref())
-        .or(gemfile_ruby.as_ref());
+    // Check if versions match (same precedence as the version resolver)
+    let default_version = Config::load().ok().and_then(|c| resolved_default(&c));
+    let project_version = ruby::find_project_ruby(&current_dir).map(|found| found.version);
+    let project_version = project_version.as_ref();
 
     let version_match = match (project_version, &default_version) {
-        (Some(pv), Some(dv)) => pv == dv,
+        (Some(pv), Some(dv)) => ruby::VersionRequirement::parse(pv)
+            .map(|req| req.matches(dv))
+            .unwrap_or(false),
         (None, _) => true, // No project version specified is OK
         (Some(_), None) => false,
     };
@@ -6,7 +6,7 @@ mod ai;
 mod checks;
 mod report;
 
-use crate::util::ui;
+use crate::{build, util::ui};
 use anyhow::Result;
 
 /// Run the doctor command
@@ -76,6 +76,11 @@ fn print_report(report: &report::DiagnosticReport, verbose: bool) {
             }
         } else if let Some(ref default) = report.ruby_status.default_version {
             ui::success(&format!("Default Ruby: {}", default));
+        } else {
+            ui::warn("Default Ruby is not installed");
+            if let Some(v) = report.ruby_versions.first() {
+                println!("    Run: railsup ruby default {}", v.version);
+            }
         }
     }
 
@@ -162,6 +167,7 @@ fn print_report(report: &report::DiagnosticReport, verbose: bool) {
                             );
                         }
                         println!("    railsup shell-init overrides (OK)");
+                        println!("    Reuse its Rubies: railsup ruby link --auto");
                     }
                     report::ConflictImpact::Blocking => {
                         ui::error(&format!("{} is blocking railsup", conflict.tool));
@@ -169,6 +175,7 @@ fn print_report(report: &report::DiagnosticReport, verbose: bool) {
                             println!("    Installed at {}", loc.display());
                         }
                         println!("    Use `railsup exec` or configure shell-init");
+                        println!("    Reuse its Rubies: railsup ruby link --auto");
                     }
                 }
             }
@@ -237,6 +244,36 @@ fn print_report(report: &report::DiagnosticReport, verbose: bool) {
         println!();
     }
 
+    // Source Builds section (verbose only; most users install binaries)
+    let missing_build_deps: Vec<_> = report
+        .build_dependencies
+        .iter()
+        .filter(|dep| !dep.found)
+        .collect();
+    if !verbose {
+        if !missing_build_deps.is_empty() {
+            ui::dim(&format!(
+                "Source builds: {} dependencies missing (railsup doctor --verbose)",
+                missing_build_deps.len()
+            ));
+            println!();
+        }
+    } else {
+        println!("Source Builds");
+
+        if missing_build_deps.is_empty() {
+            ui::success("Compiler, make and openssl/libyaml/zlib headers found");
+        } else {
+            for dep in &missing_build_deps {
+                ui::warn(&format!("{} not found", dep.name));
+            }
+            println!("    Needed for `railsup ruby build`. Install with:");
+            println!("    {}", build::install_hint());
+        }
+
+        println!();
+    }
+
     // Project section (if in a Rails project)
     if let Some(ref project) = report.project {
         println!("Project");
@@ -14,6 +14,7 @@ pub struct DiagnosticReport {
     pub conflicts: Vec<Conflict>,
     pub path_analysis: PathAnalysis,
     pub environment: EnvironmentCheck,
+    pub build_dependencies: Vec<BuildDependency>,
     pub project: Option<ProjectAnalysis>,
 }
 
@@ -127,6 +128,13 @@ pub struct EnvironmentCheck {
     pub issues: Vec<String>,
 }
 
+/// A tool or header needed by `railsup ruby build`
+#[derive(Debug, Serialize)]
+pub struct BuildDependency {
+    pub name: String,
+    pub found: bool,
+}
+
 /// Project-specific analysis (when in a Rails directory)
 #[derive(Debug, Serialize)]
 pub struct ProjectAnalysis {
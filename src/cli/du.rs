@@ -0,0 +1,225 @@
+//! Disk usage report for the railsup home
+//!
+//! railsup du [--json] [--top N]
+//!
+//! Shows, per Ruby version, the size of the Ruby install, its gems
+//! directory (with the largest gems) and its cached downloads, followed by
+//! everything else in the cache and the total for ~/.railsup.
+
+use crate::cli::ruby::list_installed_versions;
+use crate::download::{self, compare_versions};
+use crate::paths;
+use crate::ruby::external;
+use crate::util::disk::{dir_size, format_bytes};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Full report (also the `--json` output)
+#[derive(Debug, Serialize)]
+pub struct DiskUsage {
+    pub versions: Vec<VersionUsage>,
+    /// Cache files that don't belong to a listed version
+    pub other_cache_bytes: u64,
+    /// Everything under the railsup home
+    pub total_bytes: u64,
+}
+
+/// Space used by one Ruby version
+#[derive(Debug, Serialize)]
+pub struct VersionUsage {
+    pub version: String,
+    /// False for gem directories left behind by a removed Ruby
+    pub installed: bool,
+    /// Linked Rubies live outside railsup and aren't counted
+    pub linked: bool,
+    pub ruby_bytes: u64,
+    pub gems_bytes: u64,
+    pub cache_bytes: u64,
+    pub largest_gems: Vec<GemUsage>,
+}
+
+impl VersionUsage {
+    fn total_bytes(&self) -> u64 {
+        self.ruby_bytes + self.gems_bytes + self.cache_bytes
+    }
+}
+
+/// Space used by one installed gem
+#[derive(Debug, Serialize)]
+pub struct GemUsage {
+    pub name: String,
+    pub bytes: u64,
+}
+
+/// Run the du command
+pub fn run(json: bool, top: usize) -> Result<()> {
+    let report = collect(top)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if report.versions.is_empty() {
+        println!("No Ruby versions installed.");
+    }
+
+    for usage in &report.versions {
+        let note = if usage.linked {
+            " (linked)"
+        } else if !usage.installed {
+            " (not installed)"
+        } else {
+            ""
+        };
+        println!(
+            "{:<42} {:>10}",
+            format!("{}{}", usage.version, note),
+            format_bytes(usage.total_bytes())
+        );
+        if usage.installed && !usage.linked {
+            println!("  {:<40} {:>10}", "ruby", format_bytes(usage.ruby_bytes));
+        }
+        println!("  {:<40} {:>10}", "gems", format_bytes(usage.gems_bytes));
+        for gem in &usage.largest_gems {
+            println!("    {:<38} {:>10}", gem.name, format_bytes(gem.bytes));
+        }
+        if usage.cache_bytes > 0 {
+            println!("  {:<40} {:>10}", "cache", format_bytes(usage.cache_bytes));
+        }
+        println!();
+    }
+
+    if report.other_cache_bytes > 0 {
+        println!(
+            "{:<42} {:>10}",
+            "Other cache files",
+            format_bytes(report.other_cache_bytes)
+        );
+    }
+    println!(
+        "{:<42} {:>10}",
+        format!("Total ({})", paths::railsup_dir().display()),
+        format_bytes(report.total_bytes)
+    );
+
+    Ok(())
+}
+
+/// Measure every version with a Ruby install or a gems directory
+fn collect(top: usize) -> Result<DiskUsage> {
+    let installed = list_installed_versions()?;
+    let mut names: BTreeSet<String> = installed.iter().cloned().collect();
+    if let Ok(entries) = fs::read_dir(paths::gems_dir()) {
+        names.extend(
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_string()),
+        );
+    }
+    let mut names: Vec<String> = names.into_iter().collect();
+    names.sort_by(|a, b| compare_versions(b, a));
+
+    let cache_dir = paths::cache_dir();
+    let mut counted_cache = BTreeSet::new();
+    let mut versions = vec![];
+    for version in names {
+        let linked = external::linked_prefix(&version).is_some();
+        let gems_dir = paths::gems_version_dir(&version);
+
+        let mut cache_bytes = 0;
+        for file in download::version_cache_files(&version) {
+            cache_bytes += dir_size(&cache_dir.join(&file));
+            counted_cache.insert(file);
+        }
+
+        versions.push(VersionUsage {
+            installed: installed.contains(&version),
+            linked,
+            ruby_bytes: if linked {
+                0
+            } else {
+                dir_size(&paths::ruby_version_dir(&version))
+            },
+            gems_bytes: dir_size(&gems_dir),
+            cache_bytes,
+            largest_gems: largest_gems(&gems_dir, top),
+            version,
+        });
+    }
+
+    let other_cache_bytes = fs::read_dir(&cache_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| !counted_cache.contains(&*e.file_name().to_string_lossy()))
+                .map(|e| dir_size(&e.path()))
+                .sum()
+        })
+        .unwrap_or(0);
+
+    Ok(DiskUsage {
+        versions,
+        other_cache_bytes,
+        total_bytes: home_size(),
+    })
+}
+
+/// Size of the railsup home, plus the cache and config when the XDG layout
+/// keeps them elsewhere
+fn home_size() -> u64 {
+    let home = paths::railsup_dir();
+    let outside = [paths::cache_dir(), paths::config_file()]
+        .into_iter()
+        .filter(|p| !p.starts_with(&home))
+        .map(|p| dir_size(&p))
+        .sum::<u64>();
+    dir_size(&home) + outside
+}
+
+/// The `top` largest gems in a gem home, biggest first
+fn largest_gems(gem_home: &Path, top: usize) -> Vec<GemUsage> {
+    let Ok(entries) = fs::read_dir(gem_home.join("gems")) else {
+        return vec![];
+    };
+
+    let mut gems: Vec<GemUsage> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| GemUsage {
+            name: e.file_name().to_string_lossy().to_string(),
+            bytes: dir_size(&e.path()),
+        })
+        .collect();
+    gems.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    gems.truncate(top);
+    gems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn largest_gems_sorted_and_truncated() {
+        let temp = TempDir::new().unwrap();
+        let gems = temp.path().join("gems");
+        for (name, size) in [
+            ("rack-3.1.0", 10),
+            ("nokogiri-1.16.0", 300),
+            ("rails-8.0.1", 50),
+        ] {
+            fs::create_dir_all(gems.join(name)).unwrap();
+            fs::write(gems.join(name).join("lib.rb"), vec![0u8; size]).unwrap();
+        }
+
+        let found = largest_gems(temp.path(), 2);
+        let names: Vec<_> = found.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["nokogiri-1.16.0", "rails-8.0.1"]);
+        assert_eq!(found[0].bytes, 300);
+        assert!(largest_gems(&temp.path().join("missing"), 5).is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+//! Env command - show the environment railsup runs commands with
+//!
+//! railsup env [--show-values]
+//!
+//! Lists the variables `railsup dev` and `railsup exec` set and where each
+//! value comes from: railsup itself, a dotenv file in the Rails root, or the
+//! real environment when it takes precedence over a dotenv file. Values of
+//! dotenv variables are usually secrets, so they are masked unless
+//! `--show-values` is given.
+
+use crate::cli::bundler::{build_full_env, detect_bundle_context};
+use crate::dotenv::{self, MANAGED_VARS};
+use crate::ruby::resolve_ruby_version;
+use crate::util::ui;
+use anyhow::Result;
+use std::env;
+use std::path::Path;
+
+/// Shown in place of a masked value
+const MASK: &str = "****";
+
+/// Run the env command
+pub fn run(show_values: bool) -> Result<()> {
+    let current_dir = env::current_dir()?;
+    let bundle_ctx = detect_bundle_context(&current_dir);
+
+    if let Ok(resolved) = resolve_ruby_version() {
+        let env_vars = build_full_env(&resolved.version, &bundle_ctx);
+        let source = format!("railsup (Ruby {})", resolved.version);
+        for key in MANAGED_VARS {
+            if let Some(value) = env_vars.get(*key) {
+                print_var(key, &display_value(key, value), &source);
+            }
+        }
+    }
+
+    let Some(ctx) = bundle_ctx else {
+        ui::dim("Not in a Rails project; .env files are only loaded from a Rails root.");
+        return Ok(());
+    };
+
+    let vars = dotenv::load(&ctx.rails_root)?;
+    if vars.is_empty() {
+        ui::dim(&format!(
+            "No .env files in {} (RAILS_ENV={})",
+            ctx.rails_root.display(),
+            dotenv::rails_env()
+        ));
+        return Ok(());
+    }
+
+    for var in &vars {
+        let file = file_name(&var.source);
+        if var.overridden {
+            let value = env::var(&var.key).unwrap_or_default();
+            print_var(
+                &var.key,
+                &mask(&value, show_values),
+                &format!("environment (overrides {})", file),
+            );
+        } else if !var.applies() {
+            print_var(
+                &var.key,
+                &mask(&var.value, show_values),
+                &format!("{} (ignored, set by railsup)", file),
+            );
+        } else {
+            print_var(&var.key, &mask(&var.value, show_values), &file);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_var(key: &str, value: &str, source: &str) {
+    println!("{}={}  # {}", key, value.escape_debug(), source);
+}
+
+/// Hide a non-empty value unless values were asked for
+fn mask(value: &str, show_values: bool) -> String {
+    if show_values || value.is_empty() {
+        value.to_string()
+    } else {
+        MASK.to_string()
+    }
+}
+
+/// Show only what railsup adds in front of the inherited PATH
+fn display_value(key: &str, value: &str) -> String {
+    let inherited = env::var("PATH").unwrap_or_default();
+    match value.strip_suffix(inherited.as_str()) {
+        Some(prefix) if key == "PATH" && !inherited.is_empty() => format!("{}$PATH", prefix),
+        _ => value.to_string(),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
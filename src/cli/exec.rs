@@ -15,21 +15,32 @@ use crate::cli::bundler::{
     build_full_env, detect_bundle_context, format_bundle_detected_message, is_bundle_opt_out,
     wrap_command,
 };
-use crate::cli::which::resolve_ruby_version;
+use crate::config::Config;
+use crate::dotenv;
 use crate::paths;
+use crate::ruby::{resolve_installed, resolve_or_install_ruby};
+use crate::usage;
 use crate::util::ui;
 use anyhow::{bail, Result};
 
 /// Run a command with railsup Ruby environment
-pub fn run(ruby_version: Option<String>, command: Vec<String>) -> Result<()> {
+pub fn run(ruby_version: Option<String>, no_dotenv: bool, command: Vec<String>) -> Result<()> {
     if command.is_empty() {
         bail!("No command specified.\nUsage: railsup exec <command> [args...]");
     }
 
-    // 1. Resolve Ruby version
-    let version = match ruby_version {
-        Some(v) => v,
-        None => resolve_ruby_version()?,
+    // 1. Resolve Ruby version (--ruby may be an alias or requirement)
+    let (version, pin_dir) = match ruby_version {
+        Some(v) => (resolve_installed(&Config::load()?, &v)?, None),
+        None => {
+            let resolved = resolve_or_install_ruby()?;
+            let pin_dir = resolved
+                .source
+                .path()
+                .and_then(|p| p.parent())
+                .map(|p| p.to_path_buf());
+            (resolved.version, pin_dir)
+        }
     };
 
     // 2. Verify Ruby is installed
@@ -53,13 +64,29 @@ pub fn run(ruby_version: Option<String>, command: Vec<String>) -> Result<()> {
         }
     }
 
+    // Remember which Ruby this project uses
+    let project_root = bundle_ctx
+        .as_ref()
+        .map(|c| c.rails_root.clone())
+        .or(pin_dir);
+    if let Some(root) = project_root {
+        usage::record(&root, &version);
+    }
+
     // 4. Apply command wrapping (PEP-0016)
     let program = &command[0];
     let args: Vec<String> = command[1..].to_vec();
     let (wrapped_program, wrapped_args) = wrap_command(&bundle_ctx, program, &args);
 
     // 5. Build environment with bundle context
-    let env = build_full_env(&version, &bundle_ctx);
+    let mut env = build_full_env(&version, &bundle_ctx);
+
+    // Add the Rails root's .env files (real env vars still win)
+    if let Some(ref ctx) = bundle_ctx {
+        if !no_dotenv {
+            dotenv::apply(&mut env, &dotenv::load_dotenv(&ctx.rails_root)?);
+        }
+    }
 
     // Set environment variables before exec
     for (key, value) in &env {
@@ -0,0 +1,285 @@
+//! Garbage collection for the railsup home
+//!
+//! railsup gc [--dry-run] [--keep N]
+//!
+//! Removes Ruby versions nothing refers to (default, aliases, channels,
+//! the current project or a recently used one), gem directories whose Ruby
+//! is gone, and cache files for versions that are no longer installed.
+//! Installs interrupted by a previous run are finished or rolled back first.
+
+use crate::cli::ruby::{followed_channels, list_installed_versions};
+use crate::ruby::{self, external};
+use crate::util::disk::{dir_size, format_bytes};
+use crate::util::ui;
+use crate::{config::Config, download, paths, usage};
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// `.part` files younger than this may still be resumed
+const PART_FILE_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Something gc can delete
+struct Item {
+    label: String,
+    path: PathBuf,
+    size: u64,
+    /// Ruby version whose install lock guards the removal
+    version: Option<String>,
+}
+
+/// Run the gc command
+pub fn run(dry_run: bool, keep: usize) -> Result<()> {
+    if !dry_run {
+        // Finish or roll back installs interrupted by a previous run
+        download::cleanup_stale_installs(&paths::ruby_dir())?;
+    }
+
+    let config = Config::load()?;
+    let installed: Vec<String> = list_installed_versions()?
+        .into_iter()
+        .filter(|v| external::linked_prefix(v).is_none())
+        .collect();
+
+    let protected = protected_versions(&config, &installed);
+    let unused = unused_versions(&installed, &protected, keep);
+    let remaining: BTreeSet<String> = list_installed_versions()?
+        .into_iter()
+        .filter(|v| !unused.contains(v))
+        .collect();
+
+    let mut rubies = vec![];
+    for version in &unused {
+        rubies.push(item(
+            format!("Ruby {}", version),
+            paths::ruby_version_dir(version),
+            Some(version.clone()),
+        ));
+        let gems = paths::gems_version_dir(version);
+        if gems.exists() {
+            rubies.push(item(
+                format!("gems for {}", version),
+                gems,
+                Some(version.clone()),
+            ));
+        }
+    }
+    let orphans = orphaned_gem_dirs(&remaining, &unused)?;
+    let cache = stale_cache_files(&remaining)?;
+
+    let sections = [
+        ("Unused Ruby versions", &rubies),
+        ("Orphaned gem directories", &orphans),
+        ("Stale cache files", &cache),
+    ];
+    let total: u64 = sections
+        .iter()
+        .flat_map(|(_, items)| items.iter())
+        .map(|i| i.size)
+        .sum();
+
+    if sections.iter().all(|(_, items)| items.is_empty()) {
+        ui::success("Nothing to clean up");
+        return Ok(());
+    }
+
+    for (title, items) in &sections {
+        if items.is_empty() {
+            continue;
+        }
+        println!("{}", title);
+        for item in items.iter() {
+            println!("  {:<40} {:>10}", item.label, format_bytes(item.size));
+        }
+        println!();
+    }
+
+    if dry_run {
+        println!("Reclaimable: {}", format_bytes(total));
+        println!("Run without --dry-run to remove these.");
+        return Ok(());
+    }
+
+    let mut reclaimed = 0;
+    for item in sections.iter().flat_map(|(_, items)| items.iter()) {
+        // A version being installed isn't in `remaining` yet, so leave its
+        // files alone; otherwise hold its lock while they are removed
+        let _lock = match &item.version {
+            Some(version) => match download::try_install_lock(version)? {
+                Some(lock) => Some(lock),
+                None => {
+                    ui::warn(&format!(
+                        "Skipping {}: Ruby {} is being installed",
+                        item.label, version
+                    ));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let result = match fs::symlink_metadata(&item.path) {
+            Ok(m) if m.is_dir() => fs::remove_dir_all(&item.path),
+            Ok(_) => fs::remove_file(&item.path),
+            Err(_) => continue,
+        };
+        match result {
+            Ok(()) => reclaimed += item.size,
+            Err(e) => ui::warn(&format!("Failed to remove {}: {}", item.path.display(), e)),
+        }
+    }
+
+    ui::success(&format!("Reclaimed {}", format_bytes(reclaimed)));
+    Ok(())
+}
+
+fn item(label: String, path: PathBuf, version: Option<String>) -> Item {
+    let size = dir_size(&path);
+    Item {
+        label,
+        path,
+        size,
+        version,
+    }
+}
+
+/// Versions something still refers to
+fn protected_versions(config: &Config, installed: &[String]) -> BTreeSet<String> {
+    let mut protected = BTreeSet::new();
+
+    let names = config
+        .default_ruby()
+        .into_iter()
+        .map(str::to_string)
+        .chain(config.ruby.aliases.keys().cloned())
+        .chain(followed_channels(config));
+    for name in names {
+        if let Ok(version) = ruby::resolve_installed(config, &name) {
+            protected.insert(version);
+        }
+    }
+
+    if let Ok(current) = ruby::resolve_ruby_version() {
+        protected.insert(current.version);
+    }
+
+    protected.extend(usage::Usage::load().versions_in_use(usage::RECENT));
+    protected.retain(|v| installed.contains(v));
+    protected
+}
+
+/// Installed versions (newest first) that are neither protected nor among
+/// the `keep` newest of their series
+fn unused_versions(installed: &[String], protected: &BTreeSet<String>, keep: usize) -> Vec<String> {
+    let mut seen_per_series: Vec<(String, usize)> = vec![];
+    let mut unused = vec![];
+
+    for version in installed {
+        let series = download::version_series(version);
+        let position = match seen_per_series.iter_mut().find(|(s, _)| *s == series) {
+            Some((_, count)) => {
+                *count += 1;
+                *count - 1
+            }
+            None => {
+                seen_per_series.push((series, 1));
+                0
+            }
+        };
+
+        if position >= keep && !protected.contains(version) {
+            unused.push(version.clone());
+        }
+    }
+    unused
+}
+
+/// Gem directories whose Ruby isn't installed (and isn't already listed)
+fn orphaned_gem_dirs(remaining: &BTreeSet<String>, unused: &[String]) -> Result<Vec<Item>> {
+    let gems_dir = paths::gems_dir();
+    if !gems_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut items = vec![];
+    for entry in fs::read_dir(&gems_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if remaining.contains(&name) || unused.contains(&name) {
+            continue;
+        }
+        if paths::ruby_version_dir(&name).exists() {
+            continue;
+        }
+        items.push(item(name.clone(), entry.path(), Some(name)));
+    }
+    items.sort_by(|a, b| a.label.cmp(&b.label));
+    Ok(items)
+}
+
+/// Cache files not needed by any remaining version
+///
+/// Kept: the release index, tarballs and checksums of installed versions,
+/// and recent `.part` files that may still be resumed.
+fn stale_cache_files(remaining: &BTreeSet<String>) -> Result<Vec<Item>> {
+    let cache_dir = paths::cache_dir();
+    if !cache_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let keep: BTreeSet<String> = remaining
+        .iter()
+        .flat_map(|v| download::version_cache_files(v))
+        .chain([download::RELEASE_INDEX_FILE.to_string()])
+        .collect();
+
+    let mut items = vec![];
+    for entry in fs::read_dir(&cache_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if keep.contains(&name) {
+            continue;
+        }
+        if name.ends_with(".part") && modified_within(&entry.path(), PART_FILE_GRACE) {
+            continue;
+        }
+        let version = download::cache_file_version(&name);
+        items.push(item(name, entry.path(), version));
+    }
+    items.sort_by(|a, b| a.label.cmp(&b.label));
+    Ok(items)
+}
+
+fn modified_within(path: &std::path::Path, window: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| SystemTime::now().duration_since(t).ok())
+        .is_some_and(|age| age < window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(list: &[&str]) -> Vec<String> {
+        list.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn unused_versions_keeps_newest_per_series_and_protected() {
+        let installed = versions(&["3.4.2", "3.4.1", "3.4.0", "3.3.6", "3.3.5"]);
+        let protected: BTreeSet<String> = ["3.4.0".to_string()].into();
+
+        assert_eq!(
+            unused_versions(&installed, &protected, 1),
+            versions(&["3.4.1", "3.3.5"])
+        );
+        assert_eq!(unused_versions(&installed, &protected, 2), versions(&[]));
+        assert_eq!(
+            unused_versions(&installed, &protected, 0),
+            versions(&["3.4.2", "3.4.1", "3.3.6", "3.3.5"])
+        );
+    }
+}
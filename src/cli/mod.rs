@@ -9,6 +9,10 @@ pub struct Cli {
     #[arg(long)]
     pub agent: bool,
 
+    /// Never touch the network; install only from the local download cache
+    #[arg(long, global = true)]
+    pub offline: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -31,9 +35,17 @@ pub enum Commands {
 
     /// Start the development server
     Dev {
-        /// Port to run on
-        #[arg(short, long, default_value = "3000")]
-        port: u16,
+        /// Port to run on (default: $PORT, then 3000)
+        #[arg(short, long)]
+        port: Option<u16>,
+
+        /// Keep the other processes running when one exits
+        #[arg(long)]
+        keep_alive: bool,
+
+        /// Don't load .env files from the Rails root
+        #[arg(long)]
+        no_dotenv: bool,
     },
 
     /// Manage Ruby versions
@@ -48,15 +60,26 @@ pub enum Commands {
 
     /// Run a command with railsup Ruby environment
     Exec {
-        /// Ruby version to use (default: auto-detect)
+        /// Ruby version or alias to use (default: auto-detect)
         #[arg(long)]
         ruby: Option<String>,
 
+        /// Don't load .env files from the Rails root
+        #[arg(long)]
+        no_dotenv: bool,
+
         /// Command and arguments to run
         #[arg(trailing_var_arg = true, required = true)]
         command: Vec<String>,
     },
 
+    /// Show the environment dev and exec use, and where each value comes from
+    Env {
+        /// Print .env values instead of masking them
+        #[arg(long)]
+        show_values: bool,
+    },
+
     /// Output shell integration script for PATH setup
     ShellInit {
         /// Shell type (zsh, bash, fish). Auto-detected if not specified.
@@ -64,6 +87,28 @@ pub enum Commands {
         shell: Option<String>,
     },
 
+    /// Remove unused Rubies, orphaned gem directories and stale cache files
+    Gc {
+        /// Show what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Always keep this many of the newest versions in each series
+        #[arg(long, default_value = "1")]
+        keep: usize,
+    },
+
+    /// Show disk usage per Ruby version, its gems and cached downloads
+    Du {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Number of largest gems to show per version
+        #[arg(long, default_value = "5")]
+        top: usize,
+    },
+
     /// Diagnose environment and troubleshoot issues
     Doctor {
         /// Output as JSON (suppresses AI analysis)
@@ -84,7 +129,10 @@ pub mod agent;
 pub mod bundler;
 pub mod dev;
 pub mod doctor;
+pub mod du;
+pub mod env;
 pub mod exec;
+pub mod gc;
 pub mod new;
 pub mod ruby;
 pub mod shell_init;
@@ -118,4 +166,21 @@ mod tests {
             _ => panic!("expected new command"),
         }
     }
+
+    #[test]
+    fn exec_no_dotenv_is_not_forwarded() {
+        let cli =
+            Cli::try_parse_from(["railsup", "exec", "--no-dotenv", "bin/rails", "--no-dotenv"])
+                .expect("should parse exec");
+
+        match cli.command {
+            Some(Commands::Exec {
+                no_dotenv, command, ..
+            }) => {
+                assert!(no_dotenv);
+                assert_eq!(command, vec!["bin/rails", "--no-dotenv"]);
+            }
+            _ => panic!("expected exec command"),
+        }
+    }
 }
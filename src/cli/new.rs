@@ -1,8 +1,10 @@
 use crate::cli::ruby::{list_installed_versions, DEFAULT_RUBY_VERSION};
-use crate::cli::which::resolve_ruby_version;
+use crate::download::compare_versions;
+use crate::ruby::{find_project_ruby, resolve_or_install_ruby};
 use crate::util::{process, ui};
-use crate::{download, paths};
+use crate::{download, paths, usage};
 use anyhow::{bail, Result};
+use std::fs;
 use std::path::Path;
 
 /// Fallback Rails version if we can't fetch from rubygems.org
@@ -23,10 +25,31 @@ fn fetch_latest_rails_version() -> Option<String> {
 }
 
 /// Get the Rails version to use (fetched or fallback)
-fn get_rails_version() -> String {
+///
+/// Offline, the newest Rails already installed for this Ruby is preferred.
+fn get_rails_version(gem_home: &Path) -> String {
+    if download::is_offline() {
+        return newest_local_rails(gem_home).unwrap_or_else(|| FALLBACK_RAILS_VERSION.to_string());
+    }
     fetch_latest_rails_version().unwrap_or_else(|| FALLBACK_RAILS_VERSION.to_string())
 }
 
+/// Find the newest Rails gem installed in a gem home
+fn newest_local_rails(gem_home: &Path) -> Option<String> {
+    fs::read_dir(gem_home.join("specifications"))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let version = name.strip_prefix("rails-")?.strip_suffix(".gemspec")?;
+            // Skip rails-html-sanitizer, rails-dom-testing, etc.
+            version
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then(|| version.to_string())
+        })
+        .max_by(|a, b| compare_versions(a, b))
+}
+
 pub fn run(name: &str, force: bool, rails_args: &[String]) -> Result<()> {
     // 1. Validate name - reject path separators for safety
     validate_app_name(name)?;
@@ -45,8 +68,8 @@ pub fn run(name: &str, force: bool, rails_args: &[String]) -> Result<()> {
     }
 
     // 4. Get Rails version and ensure it's installed
-    let rails_version = get_rails_version();
     let gem_home = paths::gems_version_dir(&ruby_version);
+    let rails_version = get_rails_version(&gem_home);
     ensure_rails_installed(&ruby_bin, &gem_home, &rails_version)?;
 
     // 5. Run rails new
@@ -88,6 +111,8 @@ pub fn run(name: &str, force: bool, rails_args: &[String]) -> Result<()> {
         );
     }
 
+    usage::record(path, &ruby_version);
+
     // 6. Print success
     println!();
     ui::success(&format!("Created {}", name));
@@ -136,12 +161,24 @@ fn validate_app_name(name: &str) -> Result<()> {
 /// Ensure Ruby is available, auto-bootstrapping if needed
 pub fn ensure_ruby_available() -> Result<String> {
     // First, check if any railsup-managed Ruby is available
-    match resolve_ruby_version() {
-        Ok(version) => {
-            ui::info(&format!("Using Ruby {}", version));
-            return Ok(version);
+    match resolve_or_install_ruby() {
+        Ok(resolved) => {
+            if resolved.source.is_project() {
+                ui::info(&format!(
+                    "Using Ruby {} (from {})",
+                    resolved.version,
+                    resolved.source.label()
+                ));
+            } else {
+                ui::info(&format!("Using Ruby {}", resolved.version));
+            }
+            return Ok(resolved.version);
         }
-        Err(_) => {
+        Err(e) => {
+            // A project pins a Ruby that isn't installed - don't silently use another one
+            if find_project_ruby(&std::env::current_dir()?).is_some() {
+                return Err(e);
+            }
             // No Ruby installed, auto-bootstrap
         }
     }
@@ -183,15 +220,22 @@ fn ensure_rails_installed(ruby_bin: &Path, gem_home: &Path, rails_version: &str)
     // 1. Subprocesses (native extension compilation) use our Ruby
     // 2. Gems install to our gem_home directory
     ui::info(&format!("Installing Rails {}...", rails_version));
-    let status = process::run_streaming_with_full_env(
-        gem_str,
-        &["install", "rails", "-v", rails_version, "--no-document"],
-        None,
-        Some(ruby_bin),
-        Some(gem_home),
-    )?;
+    let mut args = vec!["install", "rails", "-v", rails_version, "--no-document"];
+    let offline = download::is_offline();
+    if offline {
+        args.push("--local");
+    }
+    let status =
+        process::run_streaming_with_full_env(gem_str, &args, None, Some(ruby_bin), Some(gem_home))?;
 
     if !status.success() {
+        if offline {
+            bail!(
+                "Rails {} is not available from local gems and offline mode is on.\n  \
+                 Run without --offline once to install it.",
+                rails_version
+            );
+        }
         bail!(
             "Failed to install Rails {}.\n  \
              Try running manually: {} install rails -v {}",
@@ -259,4 +303,22 @@ mod tests {
         assert!(validate_app_name(".hidden").is_err());
         assert!(validate_app_name(".myapp").is_err());
     }
+
+    #[test]
+    fn newest_local_rails_ignores_other_rails_gems() {
+        let dir = tempfile::tempdir().unwrap();
+        let specs = dir.path().join("specifications");
+        fs::create_dir_all(&specs).unwrap();
+        for name in [
+            "rails-7.2.2.gemspec",
+            "rails-8.0.1.gemspec",
+            "rails-html-sanitizer-1.6.0.gemspec",
+            "railties-8.1.0.gemspec",
+        ] {
+            fs::write(specs.join(name), "").unwrap();
+        }
+
+        assert_eq!(newest_local_rails(dir.path()), Some("8.0.1".to_string()));
+        assert_eq!(newest_local_rails(&dir.path().join("missing")), None);
+    }
 }
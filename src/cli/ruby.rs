@@ -1,14 +1,23 @@
 //! Ruby version management commands
 //!
 //! railsup ruby install <version>
+//! railsup ruby build <version> [--source <path|url>]
+//! railsup ruby link <name> <prefix> | --auto
+//! railsup ruby alias [<name> <version>] [--remove]
+//! railsup ruby upgrade [--series X.Y] [--all] [--gems copy|reinstall]
 //! railsup ruby list [--available]
 //! railsup ruby default <version>
 //! railsup ruby remove <version>
 
-use crate::{config::Config, download, paths, util::ui};
+use crate::download::compare_versions;
+use crate::ruby::external;
+use crate::usage::{self, Usage};
+use crate::{build, config::Config, download, paths, platform, ruby, util::ui};
 use anyhow::{bail, Result};
-use clap::Subcommand;
+use clap::{Subcommand, ValueEnum};
 use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
 
 /// Default Ruby version for auto-bootstrap (fetched at runtime, fallback)
 pub const DEFAULT_RUBY_VERSION: &str = "4.0.1";
@@ -17,7 +26,7 @@ pub const DEFAULT_RUBY_VERSION: &str = "4.0.1";
 pub enum RubyCommands {
     /// Install a Ruby version
     Install {
-        /// Ruby version to install (e.g., 4.0.1)
+        /// Ruby version to install (e.g., 4.0.1, 3.4, "~> 3.3", latest)
         version: String,
 
         /// Force reinstall even if already installed
@@ -25,59 +34,197 @@ pub enum RubyCommands {
         force: bool,
     },
 
+    /// Compile a Ruby version from source
+    Build {
+        /// Ruby version to build (e.g., 3.4.1, 3.5.0-preview1)
+        version: String,
+
+        /// Source tarball to build from (path or URL; defaults to ruby-lang.org)
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Parallel make jobs (defaults to the number of CPUs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Force rebuild even if already installed
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Use a Ruby installed elsewhere (rbenv, asdf, mise, rvm, Homebrew, ...)
+    Link {
+        /// Version name to register it under (e.g., 3.3.6)
+        #[arg(required_unless_present = "auto")]
+        name: Option<String>,
+
+        /// Install prefix containing bin/ruby
+        #[arg(required_unless_present = "auto")]
+        prefix: Option<PathBuf>,
+
+        /// Link every Ruby found from rbenv, asdf, mise, rvm and Homebrew
+        #[arg(long, conflicts_with_all = ["name", "prefix"])]
+        auto: bool,
+
+        /// Replace an existing link with the same name
+        #[arg(short, long)]
+        force: bool,
+    },
+
     /// List installed Ruby versions
     List {
         /// Show available versions for download
         #[arg(long)]
         available: bool,
+
+        /// Re-check the release list instead of using the cached copy
+        #[arg(long)]
+        refresh: bool,
     },
 
     /// Set the default Ruby version
     Default {
-        /// Ruby version to set as default
+        /// Ruby version, alias or channel to set as default
         version: String,
     },
 
+    /// List, set or remove version aliases
+    Alias {
+        /// Alias name (e.g., work)
+        name: Option<String>,
+
+        /// Version, requirement or channel it stands for (e.g., 3.3.6, latest-3.3)
+        #[arg(conflicts_with = "remove")]
+        version: Option<String>,
+
+        /// Remove the alias
+        #[arg(long, requires = "name")]
+        remove: bool,
+    },
+
+    /// Move to the newest patch release and migrate the default, gems and project pin
+    ///
+    /// Without flags, upgrades the Ruby in effect here plus any followed
+    /// channels (latest, latest-X.Y).
+    Upgrade {
+        /// Upgrade this series to its newest patch (e.g., 3.4)
+        #[arg(long, conflicts_with = "all")]
+        series: Option<String>,
+
+        /// Upgrade every installed series
+        #[arg(long)]
+        all: bool,
+
+        /// Bring gems over from the old version
+        #[arg(long, value_enum)]
+        gems: Option<GemMigration>,
+
+        /// Rewrite the project's version file without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
     /// Remove an installed Ruby version
     Remove {
         /// Ruby version to remove
         version: String,
+
+        /// Remove even if projects still use it
+        #[arg(short, long)]
+        force: bool,
     },
 
+    /// Show the Ruby version in effect and where it was selected
+    Current,
+
     /// Clear the download cache
     ClearCache,
 }
 
+/// How `ruby upgrade` carries gems over to the new version
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GemMigration {
+    /// Copy the gem directory (fast; fine within a series)
+    Copy,
+    /// Reinstall each gem with the new Ruby
+    Reinstall,
+}
+
 /// Handle Ruby subcommands
 pub fn run(cmd: RubyCommands) -> Result<()> {
     match cmd {
         RubyCommands::Install { version, force } => install(&version, force),
-        RubyCommands::List { available } => list(available),
+        RubyCommands::Build {
+            version,
+            source,
+            jobs,
+            force,
+        } => build(&version, source.as_deref(), jobs, force),
+        RubyCommands::Link {
+            name,
+            prefix,
+            auto,
+            force,
+        } => match (name, prefix) {
+            (Some(name), Some(prefix)) if !auto => link(&name, &prefix, force),
+            _ => link_auto(force),
+        },
+        RubyCommands::List { available, refresh } => list(available, refresh),
         RubyCommands::Default { version } => set_default(&version),
-        RubyCommands::Remove { version } => remove(&version),
+        RubyCommands::Alias {
+            name,
+            version,
+            remove,
+        } => alias(name, version, remove),
+        RubyCommands::Upgrade {
+            series,
+            all,
+            gems,
+            yes,
+        } => upgrade(series.as_deref(), all, gems, yes),
+        RubyCommands::Remove { version, force } => remove(&version, force),
+        RubyCommands::Current => current(),
         RubyCommands::ClearCache => clear_cache(),
     }
 }
 
 /// Install a Ruby version
 fn install(version: &str, force: bool) -> Result<()> {
-    // Handle "latest" keyword
-    let version = if version == "latest" {
-        match download::fetch_available_versions() {
+    // Resolve aliases, channels ("latest", "latest-3.3") and requirements
+    // like "3.4" or "~> 3.3" to a concrete version
+    let config = Config::load()?;
+    let requirement = ruby::VersionRequirement::parse(config.expand_alias(version))?;
+    let channel = requirement.is_channel().then(|| requirement.to_string());
+    let version = match requirement {
+        ruby::VersionRequirement::Exact(version) => version,
+        ruby::VersionRequirement::Latest => match download::fetch_available_versions() {
             Ok(versions) if !versions.is_empty() => versions[0].clone(),
             _ => DEFAULT_RUBY_VERSION.to_string(),
-        }
-    } else {
-        version.to_string()
+        },
+        _ => match requirement.latest_available()? {
+            Some(version) => version,
+            None => bail!(
+                "No available Ruby version matches '{}'.\nSee: railsup ruby list --available",
+                requirement
+            ),
+        },
     };
 
     ui::info(&format!("Installing Ruby {}...", version));
 
+    platform::check_compatible()?;
+
     // Check if version is available before attempting download
     match download::is_version_available(&version) {
         Ok(true) => {
             // Version is available, proceed with download
         }
+        Ok(false) if download::is_offline() => {
+            // Not cached - download_ruby explains what is available offline
+        }
+        Ok(false) if platform::detect_libc() == Some(platform::Libc::Musl) => {
+            // No musl build - download_ruby explains the platform mismatch
+        }
         Ok(false) => {
             // Version not available - guide user to latest in series
             let series = download::version_series(&version);
@@ -113,23 +260,117 @@ fn install(version: &str, force: bool) -> Result<()> {
 
     ui::success(&format!("Ruby {} installed successfully", version));
 
-    // Set as default if it's the first/only version
+    // Remember the channel so `ruby upgrade` can move it forward
+    if let Some(channel) = channel {
+        Config::update(|config| {
+            config.track_channel(&channel);
+            Ok(())
+        })?;
+    }
+
+    set_default_if_only(&version)
+}
+
+/// Build a Ruby version from source
+fn build(version: &str, source: Option<&str>, jobs: Option<usize>, force: bool) -> Result<()> {
+    ui::info(&format!("Building Ruby {} from source...", version));
+
+    build::build_ruby(version, source, jobs, force)?;
+
+    ui::success(&format!("Ruby {} built and installed", version));
+    set_default_if_only(version)
+}
+
+/// Set `version` as the default if it's the first/only installed version
+fn set_default_if_only(version: &str) -> Result<()> {
     let installed = list_installed_versions()?;
     if installed.len() == 1 {
-        let mut config = Config::load()?;
-        config.set_default_ruby(&version);
-        config.save()?;
+        Config::update(|config| {
+            config.set_default_ruby(version);
+            Ok(())
+        })?;
         println!("  Set as default Ruby version");
     }
 
     Ok(())
 }
 
+/// Link a single external Ruby
+fn link(name: &str, prefix: &std::path::Path, force: bool) -> Result<()> {
+    match external::link(name, prefix, force)? {
+        external::LinkResult::Linked => {
+            ui::success(&format!("Linked Ruby {} -> {}", name, prefix.display()))
+        }
+        external::LinkResult::AlreadyLinked => {
+            println!("Ruby {} is already linked to {}", name, prefix.display())
+        }
+    }
+    set_default_if_only(name)
+}
+
+/// Link every Ruby found from other version managers
+fn link_auto(force: bool) -> Result<()> {
+    let found = external::discover();
+    if found.is_empty() {
+        println!("No Rubies found from rbenv, asdf, mise, rvm or Homebrew.");
+        return Ok(());
+    }
+
+    let mut linked = 0;
+    let mut seen = vec![];
+    for ruby in &found {
+        if seen.contains(&ruby.version) {
+            ui::dim(&format!(
+                "Skipped {} from {} (already taken by another install)",
+                ruby.version, ruby.manager
+            ));
+            continue;
+        }
+        seen.push(ruby.version.clone());
+
+        match external::link(&ruby.version, &ruby.prefix, force) {
+            Ok(external::LinkResult::Linked) => {
+                ui::success(&format!(
+                    "Linked {} from {} ({})",
+                    ruby.version,
+                    ruby.manager,
+                    ruby.prefix.display()
+                ));
+                linked += 1;
+            }
+            Ok(external::LinkResult::AlreadyLinked) => ui::dim(&format!(
+                "{} from {} already linked",
+                ruby.version, ruby.manager
+            )),
+            Err(e) => ui::warn(&format!(
+                "Skipped {} from {}: {}",
+                ruby.version, ruby.manager, e
+            )),
+        }
+    }
+
+    println!();
+    println!("Linked {} Ruby version(s).", linked);
+    if let Some(first) = list_installed_versions()?.first() {
+        if linked > 0 {
+            set_default_if_only(first)?;
+        }
+    }
+    Ok(())
+}
+
 /// List installed or available Ruby versions
-fn list(show_available: bool) -> Result<()> {
+fn list(show_available: bool, refresh: bool) -> Result<()> {
     if show_available {
-        println!("Available Ruby versions (from GitHub):");
-        match download::fetch_available_versions() {
+        if download::is_offline() {
+            println!("Cached Ruby versions (offline):");
+        } else {
+            println!(
+                "Available Ruby versions (from {}):",
+                download::ReleaseSource::current()
+            );
+        }
+        match download::available_versions(refresh) {
             Ok(versions) => {
                 for version in &versions {
                     let series = download::version_series(version);
@@ -149,7 +390,7 @@ fn list(show_available: bool) -> Result<()> {
     if installed.is_empty() {
         println!("No Ruby versions installed.");
         // Try to get the latest available version
-        let default = match download::fetch_available_versions() {
+        let default = match download::available_versions(refresh) {
             Ok(versions) if !versions.is_empty() => versions[0].clone(),
             _ => DEFAULT_RUBY_VERSION.to_string(),
         };
@@ -158,10 +399,19 @@ fn list(show_available: bool) -> Result<()> {
     }
 
     let config = Config::load()?;
-    let default_version = config.default_ruby();
+    let default_version = config
+        .default_ruby()
+        .and_then(|name| ruby::resolve_installed(&config, name).ok());
+    let default_version = default_version.as_deref();
+
+    // Fetch available versions to check for updates (skipped offline)
+    let available = if download::is_offline() {
+        None
+    } else {
+        download::available_versions(refresh).ok()
+    };
 
-    // Fetch available versions to check for updates
-    let available = download::fetch_available_versions().ok();
+    let usage = Usage::load();
 
     println!("Installed Ruby versions:");
     for version in &installed {
@@ -183,16 +433,27 @@ fn list(show_available: bool) -> Result<()> {
             None
         };
 
+        // Linked Rubies are updated by the tool that installed them
+        let (mut notes, update_hint) = match external::linked_prefix(version) {
+            Some(prefix) => (format!(" (linked: {})", prefix.display()), None),
+            None => (String::new(), update_hint),
+        };
+
+        let projects = usage.projects_using(version).len();
+        if projects > 0 {
+            notes.push_str(&format!(" (used by {})", plural(projects, "project")));
+        }
+
         if is_default {
             if let Some(hint) = update_hint {
-                println!("  {} (default){}", version, hint);
+                println!("  {} (default){}{}", version, notes, hint);
             } else {
-                println!("  {} (default)", version);
+                println!("  {} (default){}", version, notes);
             }
         } else if let Some(hint) = update_hint {
-            println!("  {}{}", version, hint);
+            println!("  {}{}{}", version, notes, hint);
         } else {
-            println!("  {}", version);
+            println!("  {}{}", version, notes);
         }
     }
 
@@ -200,35 +461,380 @@ fn list(show_available: bool) -> Result<()> {
 }
 
 /// Set the default Ruby version
+///
+/// Aliases and channels are stored as given, so the default follows them.
 fn set_default(version: &str) -> Result<()> {
-    // Check if version is installed
-    let version_dir = paths::ruby_version_dir(version);
-    if !version_dir.exists() {
+    let resolved = ruby::resolve_installed(&Config::load()?, version)?;
+    Config::update(|config| {
+        config.set_default_ruby(version);
+        Ok(())
+    })?;
+
+    if resolved == version {
+        ui::success(&format!("Default Ruby version set to {}", version));
+    } else {
+        ui::success(&format!(
+            "Default Ruby version set to {} (currently {})",
+            version, resolved
+        ));
+    }
+    Ok(())
+}
+
+/// List, set or remove aliases in config.toml
+fn alias(name: Option<String>, version: Option<String>, remove: bool) -> Result<()> {
+    let config = Config::load()?;
+
+    let Some(name) = name else {
+        if config.ruby.aliases.is_empty() {
+            println!("No aliases defined.");
+            println!("Add one with: railsup ruby alias <name> <version>");
+        } else {
+            for (name, target) in &config.ruby.aliases {
+                match ruby::resolve_installed(&config, name) {
+                    Ok(version) if version != *target => {
+                        println!("  {} -> {} ({})", name, target, version)
+                    }
+                    Ok(_) => println!("  {} -> {}", name, target),
+                    Err(_) => println!("  {} -> {} (not installed)", name, target),
+                }
+            }
+        }
+        return Ok(());
+    };
+
+    if remove {
+        Config::update(|config| match config.ruby.aliases.remove(&name) {
+            Some(_) => Ok(()),
+            None => bail!("No alias named '{}'", name),
+        })?;
+        ui::success(&format!("Removed alias {}", name));
+        return Ok(());
+    }
+
+    let Some(target) = version else {
+        match config.ruby.aliases.get(&name) {
+            Some(target) => println!("{}", target),
+            None => bail!("No alias named '{}'", name),
+        }
+        return Ok(());
+    };
+
+    validate_alias_name(&name)?;
+    ruby::VersionRequirement::parse(&target)?;
+    Config::update(|config| {
+        if config.ruby.aliases.contains_key(&target) {
+            bail!(
+                "Aliases can't point at other aliases ('{}' is an alias)",
+                target
+            );
+        }
+        config.ruby.aliases.insert(name.clone(), target.clone());
+        Ok(())
+    })?;
+    ui::success(&format!("Alias {} -> {}", name, target));
+    Ok(())
+}
+
+/// Alias names must not be mistaken for versions or channels
+fn validate_alias_name(name: &str) -> Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && name != "latest"
+        && !name.starts_with("latest-")
+        && !name.starts_with("ruby-");
+    if !valid {
         bail!(
-            "Ruby {} is not installed.\nRun: railsup ruby install {}",
-            version,
-            version
+            "Invalid alias name '{}': use letters, digits, '-', '_' or '.', starting with a letter \
+             (and not 'latest' or 'ruby-')",
+            name
         );
     }
+    Ok(())
+}
+
+/// Channels `ruby upgrade` keeps current: installed ones plus any the
+/// default or an alias points at
+pub fn followed_channels(config: &Config) -> Vec<String> {
+    let mut channels = config.ruby.channels.clone();
+    let referenced = config
+        .default_ruby()
+        .into_iter()
+        .chain(config.ruby.aliases.values().map(String::as_str));
+    for name in referenced {
+        let target = config.expand_alias(name);
+        let is_channel = ruby::VersionRequirement::parse(target)
+            .map(|req| req.is_channel())
+            .unwrap_or(false);
+        if is_channel && !channels.iter().any(|c| c == target) {
+            channels.push(target.to_string());
+        }
+    }
+    channels
+}
+
+/// Pick what `ruby upgrade` should move forward, as (label, requirement)
+fn upgrade_targets(
+    config: &Config,
+    installed: &[String],
+    series: Option<&str>,
+    all: bool,
+) -> Result<Vec<(String, ruby::VersionRequirement)>> {
+    let mut labels = vec![];
+    if let Some(series) = series {
+        labels.push(series.to_string());
+    } else if all {
+        labels.extend(installed.iter().map(|v| download::version_series(v)));
+    } else {
+        if let Ok(current) = ruby::resolve_ruby_version() {
+            if external::linked_prefix(&current.version).is_none() {
+                labels.push(download::version_series(&current.version));
+            }
+        }
+        labels.extend(followed_channels(config));
+    }
 
-    let mut config = Config::load()?;
-    config.set_default_ruby(version);
-    config.save()?;
+    let mut targets: Vec<(String, ruby::VersionRequirement)> = vec![];
+    for label in labels {
+        if targets.iter().any(|(l, _)| *l == label) {
+            continue;
+        }
+        let requirement = ruby::VersionRequirement::parse(&label)?;
+        if requirement.is_exact() {
+            bail!("Expected a series like 3.4, got '{}'", label);
+        }
+        targets.push((label, requirement));
+    }
+    Ok(targets)
+}
+
+/// Install newer patch releases and migrate what pointed at the old ones
+fn upgrade(series: Option<&str>, all: bool, gems: Option<GemMigration>, yes: bool) -> Result<()> {
+    let config = Config::load()?;
+
+    // Linked Rubies are upgraded by the tool that installed them
+    let installed: Vec<String> = list_installed_versions()?
+        .into_iter()
+        .filter(|v| external::linked_prefix(v).is_none())
+        .collect();
+
+    let targets = upgrade_targets(&config, &installed, series, all)?;
+    if targets.is_empty() {
+        println!("Nothing to upgrade.");
+        println!("Install a Ruby first, or follow a channel: railsup ruby install latest");
+        return Ok(());
+    }
+
+    let available = download::fetch_available_versions()?;
+    let mut moved = vec![];
+    let mut summary = vec![];
+
+    for (label, requirement) in &targets {
+        let current = requirement.best_match(&installed);
+        let Some(latest) = requirement.best_match(&available) else {
+            ui::warn(&format!("{}: no available release matches", label));
+            continue;
+        };
+
+        match current {
+            Some(ref current) if compare_versions(current, &latest).is_ge() => {
+                println!("  {}: {} (up to date)", label, current);
+            }
+            _ => {
+                ui::info(&format!("Upgrading {} to Ruby {}...", label, latest));
+                download::download_ruby(&latest, false)?;
+                summary.push(format!(
+                    "{}: {} -> {}",
+                    label,
+                    current.as_deref().unwrap_or("none"),
+                    latest
+                ));
+                // Only a patch upgrade migrates; a channel like `latest`
+                // that crosses into a new series leaves everything in place
+                if let Some(current) = current {
+                    let same_series =
+                        download::version_series(&current) == download::version_series(&latest);
+                    if same_series && !moved.iter().any(|(old, _)| *old == current) {
+                        moved.push((current, latest));
+                    }
+                }
+            }
+        }
+    }
+
+    for (old, new) in &moved {
+        // Default (an alias or channel default follows on its own)
+        let moved_default = Config::update(|config| {
+            let matches = config.default_ruby() == Some(old.as_str());
+            if matches {
+                config.set_default_ruby(new);
+            }
+            Ok(matches)
+        })?;
+        if moved_default {
+            summary.push(format!("default: {} -> {}", old, new));
+        }
+
+        // Gems
+        let old_gems = ruby::gems::installed_gems(&paths::gems_version_dir(old))?.len();
+        match gems {
+            Some(GemMigration::Copy) => {
+                let count = ruby::gems::copy_gems(old, new)?;
+                summary.push(format!("gems: copied {} from {} to {}", count, old, new));
+            }
+            Some(GemMigration::Reinstall) => {
+                ui::info(&format!(
+                    "Reinstalling {} gem(s) for Ruby {}...",
+                    old_gems, new
+                ));
+                let failed = ruby::gems::reinstall_gems(old, new)?;
+                summary.push(format!(
+                    "gems: reinstalled {} of {} for {}",
+                    old_gems - failed.len(),
+                    old_gems,
+                    new
+                ));
+                for gem in failed {
+                    summary.push(format!("  failed: {}", gem));
+                }
+            }
+            None if old_gems > 0 => summary.push(format!(
+                "gems: {} left with {} (use --gems copy or --gems reinstall)",
+                old_gems, old
+            )),
+            None => {}
+        }
+
+        // Project pin in the current directory
+        if let Some(message) = upgrade_project_pin(old, new, yes)? {
+            summary.push(message);
+        }
+    }
+
+    println!();
+    if summary.is_empty() {
+        ui::success("Everything is up to date");
+        return Ok(());
+    }
+
+    println!("Summary");
+    for line in &summary {
+        println!("  {}", line);
+    }
+    if !moved.is_empty() {
+        println!();
+        println!("Previous versions are still installed. Remove them with:");
+        for (old, _) in &moved {
+            println!("  railsup ruby remove {}", old);
+        }
+    }
 
-    ui::success(&format!("Default Ruby version set to {}", version));
     Ok(())
 }
 
+/// Offer to move the current project's exact pin from `old` to `new`
+fn upgrade_project_pin(old: &str, new: &str, yes: bool) -> Result<Option<String>> {
+    let current_dir = std::env::current_dir()?;
+    let Some(project) = ruby::find_project_ruby(&current_dir) else {
+        return Ok(None);
+    };
+    if project.version != old {
+        return Ok(None);
+    }
+
+    let source = project.source;
+    let rewrite = if yes {
+        true
+    } else if io::stdin().is_terminal() && io::stderr().is_terminal() {
+        confirm(&format!("Update {} from {} to {}?", source, old, new))?
+    } else {
+        return Ok(Some(format!(
+            "{}: still pins {} (rerun with --yes to update it)",
+            source, old
+        )));
+    };
+
+    if !rewrite {
+        return Ok(Some(format!("{}: left at {}", source, old)));
+    }
+    if ruby::rewrite_project_pin(&source, old, new)? {
+        Ok(Some(format!("{}: {} -> {}", source, old, new)))
+    } else {
+        Ok(Some(format!("{}: update the Ruby version by hand", source)))
+    }
+}
+
+/// Ask a yes/no question (defaults to yes)
+///
+/// Asked on stderr, so commands whose stdout is captured can still prompt.
+pub(crate) fn confirm(question: &str) -> Result<bool> {
+    eprint!("{} [Y/n] ", question);
+    io::stderr().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let answer = input.trim().to_lowercase();
+    Ok(answer.is_empty() || answer == "y" || answer == "yes")
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
 /// Remove an installed Ruby version
-fn remove(version: &str) -> Result<()> {
+fn remove(version: &str, force: bool) -> Result<()> {
     let version_dir = paths::ruby_version_dir(version);
-    if !version_dir.exists() {
+    if !version_dir.exists() && external::linked_prefix(version).is_none() {
         bail!("Ruby {} is not installed", version);
     }
 
-    // Remove Ruby directory
-    ui::info(&format!("Removing Ruby {}...", version));
-    fs::remove_dir_all(&version_dir)?;
+    let usage = Usage::load();
+    let dependents = usage.projects_using(version);
+    if !dependents.is_empty() {
+        ui::warn(&format!(
+            "Ruby {} is used by {}:",
+            version,
+            plural(dependents.len(), "project")
+        ));
+        for project in &dependents {
+            println!(
+                "    {} (last used {})",
+                project.root.display(),
+                usage::format_last_used(project.last_used)
+            );
+        }
+        if !force {
+            bail!("Use --force to remove it anyway");
+        }
+    }
+
+    // Resolve an alias or channel default before the version disappears
+    let config = Config::load()?;
+    let was_default = config
+        .default_ruby()
+        .and_then(|name| ruby::resolve_installed(&config, name).ok())
+        .is_some_and(|default| default == version);
+
+    // A concurrent install of the same version must not lose its files
+    let _lock = download::install_lock(version)?;
+
+    // Remove Ruby directory (a linked Ruby only loses its link)
+    let linked = external::linked_prefix(version);
+    if let Some(ref prefix) = linked {
+        ui::info(&format!("Unlinking Ruby {}...", version));
+        fs::remove_file(&version_dir)?;
+        println!("  Left {} untouched", prefix.display());
+    } else {
+        ui::info(&format!("Removing Ruby {}...", version));
+        fs::remove_dir_all(&version_dir)?;
+    }
 
     // Remove gems directory if it exists
     let gems_dir = paths::gems_version_dir(version);
@@ -237,8 +843,7 @@ fn remove(version: &str) -> Result<()> {
     }
 
     // Check if this was the default and warn user
-    let config = Config::load()?;
-    if config.default_ruby() == Some(version) {
+    if was_default {
         println!("  Note: This was the default version. Set a new default with:");
         println!("    railsup ruby default <version>");
     }
@@ -247,6 +852,13 @@ fn remove(version: &str) -> Result<()> {
     Ok(())
 }
 
+/// Show the Ruby version that would be used in the current directory
+fn current() -> Result<()> {
+    let resolved = ruby::resolve_ruby_version()?;
+    println!("{} (from {})", resolved.version, resolved.source);
+    Ok(())
+}
+
 /// Clear the download cache
 fn clear_cache() -> Result<()> {
     let cache_dir = paths::cache_dir();
@@ -295,7 +907,8 @@ pub fn list_installed_versions() -> Result<Vec<String>> {
     let mut versions = vec![];
     for entry in fs::read_dir(ruby_dir)? {
         let entry = entry?;
-        if entry.file_type()?.is_dir() {
+        // Follow symlinks so linked Rubies are listed too
+        if entry.path().is_dir() {
             let name = entry.file_name().to_string_lossy().to_string();
             // Skip hidden directories
             if name.starts_with('.') {
@@ -316,24 +929,52 @@ pub fn list_installed_versions() -> Result<Vec<String>> {
     Ok(versions)
 }
 
-/// Compare two version strings (simple semver comparison)
-fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
-    let a_parts: Vec<u32> = a.split('.').filter_map(|p| p.parse().ok()).collect();
-    let b_parts: Vec<u32> = b.split('.').filter_map(|p| p.parse().ok()).collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for (av, bv) in a_parts.iter().zip(b_parts.iter()) {
-        match av.cmp(bv) {
-            std::cmp::Ordering::Equal => continue,
-            other => return other,
-        }
+    #[test]
+    fn alias_names_cannot_look_like_versions() {
+        assert!(validate_alias_name("work").is_ok());
+        assert!(validate_alias_name("client-a_2").is_ok());
+        assert!(validate_alias_name("3.3").is_err());
+        assert!(validate_alias_name("latest").is_err());
+        assert!(validate_alias_name("latest-3.3").is_err());
+        assert!(validate_alias_name("ruby-work").is_err());
+        assert!(validate_alias_name("a/b").is_err());
     }
 
-    a_parts.len().cmp(&b_parts.len())
-}
+    #[test]
+    fn followed_channels_include_default_and_alias_targets() {
+        let config: Config = toml::from_str(
+            "[ruby]\ndefault = \"edge\"\nchannels = [\"latest\"]\n\n[ruby.aliases]\nedge = \"latest-3.4\"\nwork = \"3.3.6\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            followed_channels(&config),
+            vec!["latest".to_string(), "latest-3.4".to_string()]
+        );
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn upgrade_targets_for_series_and_all() {
+        let config = Config::default();
+        let installed = vec![
+            "3.4.1".to_string(),
+            "3.3.5".to_string(),
+            "3.3.2".to_string(),
+        ];
+
+        let targets = upgrade_targets(&config, &installed, Some("3.4"), false).unwrap();
+        let labels: Vec<_> = targets.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(labels, vec!["3.4"]);
+
+        let targets = upgrade_targets(&config, &installed, None, true).unwrap();
+        let labels: Vec<_> = targets.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(labels, vec!["3.4", "3.3"]);
+
+        assert!(upgrade_targets(&config, &installed, Some("3.4.1"), false).is_err());
+    }
 
     #[test]
     fn compare_versions_works() {
@@ -6,8 +6,9 @@
 //! Users add `eval "$(railsup shell-init)"` to their shell profile.
 
 use crate::cli::ruby::list_installed_versions;
-use crate::config::Config;
 use crate::paths;
+use crate::ruby::{resolve_global, resolve_ruby_version};
+use crate::util::ui;
 use anyhow::{bail, Result};
 use std::env;
 use std::path::Path;
@@ -33,32 +34,31 @@ fn detect_shell_from_env(shell_var: Option<String>) -> String {
         .unwrap_or_else(|| "bash".to_string())
 }
 
-/// Resolve the default Ruby version to use
+/// Resolve the Ruby version to use for the shell
+///
+/// Uses the shared resolver, so a project pin in the current directory wins.
+/// A pinned-but-missing version only warns: failing here would break shell startup.
 fn resolve_default_version() -> Result<String> {
-    // 1. Check global default
-    if let Ok(config) = Config::load() {
-        if let Some(default) = config.default_ruby() {
-            let version_dir = paths::ruby_version_dir(default);
-            if version_dir.exists() {
-                return Ok(default.to_string());
+    // 1. Project pin, global default, or latest installed
+    match resolve_ruby_version() {
+        Ok(resolved) => return Ok(resolved.version),
+        Err(e) => {
+            if list_installed_versions()?.is_empty() {
+                // 2. No Ruby installed
+                bail!(
+                    "No Ruby version installed.\n\n\
+                     Install Ruby first:\n  \
+                     railsup ruby install 4.0.1\n\n\
+                     Then add shell integration:\n  \
+                     eval \"$(railsup shell-init)\""
+                );
             }
+            ui::warn(&format!("{:#}", e));
         }
     }
 
-    // 2. Use latest installed
-    let installed = list_installed_versions()?;
-    if let Some(version) = installed.first() {
-        return Ok(version.clone());
-    }
-
-    // 3. No Ruby installed
-    bail!(
-        "No Ruby version installed.\n\n\
-         Install Ruby first:\n  \
-         railsup ruby install 4.0.1\n\n\
-         Then add shell integration:\n  \
-         eval \"$(railsup shell-init)\""
-    )
+    // 3. Fall back to global default or latest installed
+    Ok(resolve_global()?.version)
 }
 
 /// Generate shell initialization script
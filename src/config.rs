@@ -3,8 +3,10 @@
 //! Handles reading/writing ~/.railsup/config.toml
 
 use crate::paths;
-use anyhow::{Context, Result};
+use crate::util::lock::FileLock;
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
@@ -13,6 +15,9 @@ use std::path::Path;
 pub struct Config {
     #[serde(default)]
     pub ruby: RubyConfig,
+
+    #[serde(default, skip_serializing_if = "DownloadConfig::is_empty")]
+    pub download: DownloadConfig,
 }
 
 /// Ruby-specific configuration
@@ -20,6 +25,60 @@ pub struct Config {
 pub struct RubyConfig {
     /// Default Ruby version
     pub default: Option<String>,
+
+    /// Whether to install a project's missing Ruby automatically
+    pub auto_install: Option<AutoInstall>,
+
+    /// Named versions (`work = "3.3.6"`), usable anywhere a version is
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+
+    /// Channels installed with `ruby install` (e.g. "latest-3.3") that
+    /// `ruby upgrade` keeps current
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<String>,
+}
+
+/// Download-specific configuration
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DownloadConfig {
+    /// Base URL (or file:// directory) mirroring the Ruby binary releases
+    pub mirror: Option<String>,
+
+    /// Check release signatures (defaults to true; only a custom mirror
+    /// without `.minisig` files may turn this off)
+    pub verify_signatures: Option<bool>,
+}
+
+impl DownloadConfig {
+    fn is_empty(&self) -> bool {
+        self.mirror.is_none() && self.verify_signatures.is_none()
+    }
+}
+
+/// Policy for installing a project-pinned Ruby that isn't installed yet
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoInstall {
+    /// Install without asking
+    Always,
+    /// Ask on a terminal, fail otherwise
+    #[default]
+    Prompt,
+    /// Never install, fail with instructions
+    Never,
+}
+
+impl AutoInstall {
+    /// Parse a policy name (always, prompt, never)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "always" => Some(AutoInstall::Always),
+            "prompt" => Some(AutoInstall::Prompt),
+            "never" => Some(AutoInstall::Never),
+            _ => None,
+        }
+    }
 }
 
 impl Config {
@@ -39,8 +98,20 @@ impl Config {
             .with_context(|| format!("Failed to parse config file: {}", config_path.display()))
     }
 
-    /// Save configuration to ~/.railsup/config.toml
-    pub fn save(&self) -> Result<()> {
+    /// Load, change and save ~/.railsup/config.toml
+    ///
+    /// The lock is held from load to save, so two railsup processes changing
+    /// the config at once don't overwrite each other's changes.
+    pub fn update<T>(change: impl FnOnce(&mut Config) -> Result<T>) -> Result<T> {
+        let _lock = FileLock::acquire("config", "updating config.toml")?;
+        let mut config = Config::load()?;
+        let result = change(&mut config)?;
+        config.write()?;
+        Ok(result)
+    }
+
+    /// Write configuration to ~/.railsup/config.toml (caller holds the lock)
+    fn write(&self) -> Result<()> {
         let config_path = paths::config_file();
 
         // Ensure parent directory exists
@@ -50,7 +121,11 @@ impl Config {
 
         let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
 
-        fs::write(&config_path, content)
+        // Write a temp file and rename it so readers never see a partial file
+        let tmp = config_path.with_extension(format!("toml.{}.tmp", std::process::id()));
+        fs::write(&tmp, content)
+            .with_context(|| format!("Failed to write config file: {}", tmp.display()))?;
+        fs::rename(&tmp, &config_path)
             .with_context(|| format!("Failed to write config file: {}", config_path.display()))
     }
 
@@ -63,18 +138,93 @@ impl Config {
     pub fn set_default_ruby(&mut self, version: &str) {
         self.ruby.default = Some(version.to_string());
     }
+
+    /// Replace an alias name with its target; other names pass through
+    pub fn expand_alias<'a>(&'a self, name: &'a str) -> &'a str {
+        self.ruby
+            .aliases
+            .get(name.trim())
+            .map(String::as_str)
+            .unwrap_or(name)
+    }
+
+    /// Remember a channel so `ruby upgrade` re-resolves it
+    pub fn track_channel(&mut self, channel: &str) {
+        if !self.ruby.channels.iter().any(|c| c == channel) {
+            self.ruby.channels.push(channel.to_string());
+        }
+    }
+
+    /// Get the auto-install policy
+    /// Priority: RAILSUP_AUTO_INSTALL -> config.toml -> prompt
+    pub fn auto_install(&self) -> Result<AutoInstall> {
+        match std::env::var("RAILSUP_AUTO_INSTALL") {
+            Ok(value) if !value.is_empty() => match AutoInstall::parse(&value) {
+                Some(policy) => Ok(policy),
+                None => bail!(
+                    "Invalid RAILSUP_AUTO_INSTALL value '{}' (expected always, prompt or never)",
+                    value
+                ),
+            },
+            _ => Ok(self.ruby.auto_install.unwrap_or_default()),
+        }
+    }
 }
 
 /// Project-level configuration from railsup.toml
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ProjectConfig {
-    /// Ruby version for this project
+    /// Ruby version or requirement for this project ("3.4.1", "3.4", "~> 3.3")
     pub ruby: Option<String>,
+
+    #[serde(default)]
+    pub dev: DevConfig,
+}
+
+/// `[dev]` settings for `railsup dev`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DevConfig {
+    /// Restart policies by Procfile process name
+    /// (`[dev.restart]` with `worker = { policy = "on-failure", max_retries = 3 }`)
+    #[serde(default)]
+    pub restart: BTreeMap<String, RestartConfig>,
+
+    /// Procfile process that serves the app and gets `--port`
+    /// (defaults to "web")
+    pub web: Option<String>,
+
+    /// Procfile processes that read the terminal, for `binding.irb` and
+    /// `debugger` (`interactive = ["web"]`)
+    #[serde(default)]
+    pub interactive: Vec<String>,
+}
+
+/// When `railsup dev` restarts a process that exited
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// Leave it stopped (the default)
+    #[default]
+    Never,
+    /// Restart after a non-zero exit, up to `max_retries` times
+    OnFailure,
+    /// Restart after any exit
+    Always,
+}
+
+/// Restart settings for one process
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestartConfig {
+    #[serde(default)]
+    pub policy: RestartPolicy,
+
+    /// Give up after this many restarts in a row (on-failure defaults to 5,
+    /// always to unlimited)
+    pub max_retries: Option<u32>,
 }
 
 impl ProjectConfig {
     /// Load project config from a directory
-    #[allow(dead_code)] // Will be used in auto-bootstrap
     pub fn load_from_dir(dir: &Path) -> Result<Option<Self>> {
         let config_path = dir.join("railsup.toml");
 
@@ -120,4 +270,82 @@ mod tests {
 
         assert_eq!(loaded.default_ruby(), Some("4.0.1"));
     }
+
+    #[test]
+    fn auto_install_reads_config_key() {
+        let config: Config = toml::from_str("[ruby]\nauto_install = \"always\"\n").unwrap();
+        assert_eq!(config.ruby.auto_install, Some(AutoInstall::Always));
+
+        let config = Config::default();
+        assert_eq!(
+            config.ruby.auto_install.unwrap_or_default(),
+            AutoInstall::Prompt
+        );
+    }
+
+    #[test]
+    fn auto_install_parse() {
+        assert_eq!(AutoInstall::parse("never"), Some(AutoInstall::Never));
+        assert_eq!(AutoInstall::parse("Always"), Some(AutoInstall::Always));
+        assert_eq!(AutoInstall::parse("sometimes"), None);
+    }
+
+    #[test]
+    fn aliases_expand_and_roundtrip() {
+        let config: Config =
+            toml::from_str("[ruby]\ndefault = \"work\"\n\n[ruby.aliases]\nwork = \"3.3.6\"\n")
+                .unwrap();
+        assert_eq!(config.expand_alias("work"), "3.3.6");
+        assert_eq!(config.expand_alias("3.4.1"), "3.4.1");
+
+        let toml_str = toml::to_string_pretty(&config).unwrap();
+        let loaded: Config = toml::from_str(&toml_str).unwrap();
+        assert_eq!(
+            loaded.ruby.aliases.get("work").map(String::as_str),
+            Some("3.3.6")
+        );
+    }
+
+    #[test]
+    fn track_channel_is_idempotent() {
+        let mut config = Config::default();
+        config.track_channel("latest-3.3");
+        config.track_channel("latest-3.3");
+        assert_eq!(config.ruby.channels, vec!["latest-3.3".to_string()]);
+    }
+
+    #[test]
+    fn project_restart_policies_parse() {
+        let config: ProjectConfig = toml::from_str(
+            "ruby = \"3.4.1\"\n\n[dev.restart]\nworker = { policy = \"on-failure\", max_retries = 3 }\ncss = { policy = \"always\" }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.dev.restart.get("worker"),
+            Some(&RestartConfig {
+                policy: RestartPolicy::OnFailure,
+                max_retries: Some(3),
+            })
+        );
+        assert_eq!(config.dev.restart["css"].policy, RestartPolicy::Always);
+
+        let config: ProjectConfig = toml::from_str("ruby = \"3.4.1\"\n").unwrap();
+        assert!(config.dev.restart.is_empty());
+        assert!(config.dev.web.is_none());
+    }
+
+    #[test]
+    fn download_mirror_roundtrip() {
+        let config: Config =
+            toml::from_str("[download]\nmirror = \"https://artifacts.corp/railsup-ruby\"\n")
+                .unwrap();
+        assert_eq!(
+            config.download.mirror.as_deref(),
+            Some("https://artifacts.corp/railsup-ruby")
+        );
+
+        // No empty [download] table when nothing is set
+        let toml_str = toml::to_string_pretty(&Config::default()).unwrap();
+        assert!(!toml_str.contains("[download]"));
+    }
 }
@@ -0,0 +1,431 @@
+//! `.env` files for `railsup dev` and `railsup exec`
+//!
+//! Loads `.env`, `.env.<RAILS_ENV>` and `.env.local` from the Rails root, in
+//! that order, with later files overriding earlier ones (as dotenv-rails
+//! does). Variables already set in the real environment always win, and the
+//! variables railsup manages itself (PATH, GEM_HOME, ...) are never taken
+//! from a file.
+//!
+//! Parsing follows the dotenv gem: `#` comments, an optional `export `
+//! prefix, single quotes (literal), double quotes (escapes, may span lines)
+//! and `$VAR` / `${VAR}` interpolation outside single quotes. Lines that
+//! aren't a `KEY=VALUE` definition are skipped with a warning, as the gem
+//! skips them.
+
+use crate::util::ui;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Variables railsup sets itself; a `.env` value would break isolation
+pub const MANAGED_VARS: &[&str] = &[
+    "PATH",
+    "GEM_HOME",
+    "GEM_PATH",
+    "BUNDLE_GEMFILE",
+    "RUBYOPT",
+    "RUBYLIB",
+];
+
+/// A variable defined by a dotenv file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotenvVar {
+    pub key: String,
+    pub value: String,
+    /// The file whose definition is used
+    pub source: PathBuf,
+    /// Also set in the real environment, which takes precedence
+    pub overridden: bool,
+}
+
+impl DotenvVar {
+    /// Whether this value ends up in the environment of spawned commands
+    pub fn applies(&self) -> bool {
+        !self.overridden && !MANAGED_VARS.contains(&self.key.as_str())
+    }
+}
+
+/// The Rails environment whose `.env.<name>` file is loaded
+pub fn rails_env() -> String {
+    ["RAILS_ENV", "RACK_ENV"]
+        .iter()
+        .filter_map(|key| env::var(key).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| "development".to_string())
+}
+
+/// Dotenv files for a Rails root, lowest precedence first
+///
+/// Like dotenv-rails, `.env.local` is skipped in the test environment so
+/// test runs are the same on every machine.
+pub fn files(rails_root: &Path, rails_env: &str) -> Vec<PathBuf> {
+    let mut names = vec![".env".to_string(), format!(".env.{}", rails_env)];
+    if rails_env != "test" {
+        names.push(".env.local".to_string());
+    }
+    names
+        .into_iter()
+        .map(|name| rails_root.join(name))
+        .collect()
+}
+
+/// The dotenv files that exist in a Rails root, lowest precedence first
+pub fn existing_files(rails_root: &Path) -> Vec<PathBuf> {
+    files(rails_root, &rails_env())
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Load the dotenv files of a Rails root, one entry per variable
+pub fn load(rails_root: &Path) -> Result<Vec<DotenvVar>> {
+    let real = |key: &str| env::var(key).ok();
+    let mut loaded = BTreeMap::new();
+    let mut sources: BTreeMap<String, PathBuf> = BTreeMap::new();
+
+    for path in existing_files(rails_root) {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let parsed = parse(&content, &real, &mut loaded);
+        if !parsed.skipped.is_empty() {
+            let lines: Vec<String> = parsed.skipped.iter().map(|n| n.to_string()).collect();
+            ui::warn(&format!(
+                "Skipping {} {} of {}: expected KEY=VALUE",
+                if lines.len() == 1 { "line" } else { "lines" },
+                lines.join(", "),
+                path.display()
+            ));
+        }
+        for (key, _) in parsed.vars {
+            sources.insert(key, path.clone());
+        }
+    }
+
+    Ok(sources
+        .into_iter()
+        .map(|(key, source)| DotenvVar {
+            value: loaded.remove(&key).unwrap_or_default(),
+            overridden: env::var_os(&key).is_some(),
+            key,
+            source,
+        })
+        .collect())
+}
+
+/// Load the Rails root's dotenv files, saying which ones were found
+pub fn load_dotenv(rails_root: &Path) -> Result<Vec<DotenvVar>> {
+    let files: Vec<String> = existing_files(rails_root)
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect();
+    if !files.is_empty() {
+        ui::info(&format!("Loading {}", files.join(", ")));
+    }
+    load(rails_root)
+}
+
+/// Add the applicable dotenv variables to a command environment
+pub fn apply(env_vars: &mut HashMap<String, String>, vars: &[DotenvVar]) {
+    for var in vars.iter().filter(|v| v.applies()) {
+        env_vars.insert(var.key.clone(), var.value.clone());
+    }
+}
+
+/// The definitions found in one dotenv file
+#[derive(Debug, Default)]
+pub struct Parsed {
+    /// `(key, value)` pairs in file order
+    pub vars: Vec<(String, String)>,
+    /// Numbers of the lines that weren't a definition and were skipped
+    pub skipped: Vec<usize>,
+}
+
+/// Parse dotenv content into `(key, value)` pairs in file order
+///
+/// Each definition is also stored in `loaded`. References resolve against
+/// `real` (the process environment) first, then `loaded`, so values from
+/// earlier lines and earlier files can be interpolated.
+pub fn parse(
+    content: &str,
+    real: &dyn Fn(&str) -> Option<String>,
+    loaded: &mut BTreeMap<String, String>,
+) -> Parsed {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut parsed = Parsed::default();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line_no = index + 1;
+        let line = lines[index].trim();
+        index += 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+        let Some((key, raw)) = line.split_once('=') else {
+            parsed.skipped.push(line_no);
+            continue;
+        };
+        let key = key.trim();
+        if !is_valid_key(key) {
+            parsed.skipped.push(line_no);
+            continue;
+        }
+
+        let raw = raw.trim_start();
+        let lookup = |name: &str| real(name).or_else(|| loaded.get(name).cloned());
+        let value = match raw.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let Some((body, spanned)) = quoted_value(&raw[1..], &lines[index..], quote) else {
+                    parsed.skipped.push(line_no);
+                    continue;
+                };
+                index += spanned;
+                if quote == '\'' {
+                    body
+                } else {
+                    expand(&body, true, &lookup)
+                }
+            }
+            _ => expand(strip_comment(raw).trim_end(), false, &lookup),
+        };
+
+        loaded.insert(key.to_string(), value.clone());
+        parsed.vars.push((key.to_string(), value));
+    }
+
+    parsed
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// The body of a quoted value whose first line (after the opening quote) is
+/// `first`, and how many of the `following` lines it spans
+///
+/// `None` if the quote never closes or is followed by more than a comment.
+fn quoted_value(first: &str, following: &[&str], quote: char) -> Option<(String, usize)> {
+    let mut body = first.to_string();
+    let mut spanned = 0;
+    let end = loop {
+        if let Some(end) = closing_quote(&body, quote) {
+            break end;
+        }
+        // Quoted values may continue on the next lines
+        body.push('\n');
+        body.push_str(following.get(spanned)?);
+        spanned += 1;
+    };
+
+    let rest = body[end + 1..].trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return None;
+    }
+    body.truncate(end);
+    Some((body, spanned))
+}
+
+/// Byte offset of the quote closing a value, skipping `\"` in double quotes
+fn closing_quote(body: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in body.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote == '"' {
+            escaped = true;
+        } else if c == quote {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Drop a ` # comment` from an unquoted value
+fn strip_comment(raw: &str) -> &str {
+    if raw.starts_with('#') {
+        return "";
+    }
+    match raw.find(" #").or_else(|| raw.find("\t#")) {
+        Some(i) => &raw[..i],
+        None => raw,
+    }
+}
+
+/// Interpolate `$VAR` and `${VAR}`, and with `escapes` also turn `\n`,
+/// `\t`, `\"`, `\\` and `\$` into the characters they stand for
+fn expand(value: &str, escapes: bool, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') if escapes => out.push('\n'),
+                Some('t') if escapes => out.push('\t'),
+                Some('r') if escapes => out.push('\r'),
+                Some(next @ ('"' | '\\' | '$')) => out.push(next),
+                Some(next) => {
+                    out.push('\\');
+                    out.push(next);
+                }
+                None => out.push('\\'),
+            },
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                out.push_str(&lookup(&name).unwrap_or_default());
+            }
+            '$' if chars
+                .peek()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_') =>
+            {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                out.push_str(&lookup(&name).unwrap_or_default());
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn parse_str(content: &str) -> Vec<(String, String)> {
+        let real = |key: &str| (key == "HOME").then(|| "/home/dev".to_string());
+        parse(content, &real, &mut BTreeMap::new()).vars
+    }
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn parses_plain_and_exported_values() {
+        let vars = parse_str(
+            "# database\nDATABASE_URL=postgres://localhost/app\n\nexport REDIS_URL = redis://localhost:6379 # cache\nEMPTY=\nHASH=abc#def\n",
+        );
+        assert_eq!(
+            vars,
+            vec![
+                pair("DATABASE_URL", "postgres://localhost/app"),
+                pair("REDIS_URL", "redis://localhost:6379"),
+                pair("EMPTY", ""),
+                pair("HASH", "abc#def"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_quoted_values() {
+        let vars = parse_str(
+            "SINGLE='literal $HOME \\n'\nDOUBLE=\"line1\\nsay \\\"hi\\\" \\$HOME\"\nKEY=\"-----BEGIN\nabc\n-----END\" # pem\n",
+        );
+        assert_eq!(
+            vars,
+            vec![
+                pair("SINGLE", "literal $HOME \\n"),
+                pair("DOUBLE", "line1\nsay \"hi\" $HOME"),
+                pair("KEY", "-----BEGIN\nabc\n-----END"),
+            ]
+        );
+    }
+
+    #[test]
+    fn interpolates_environment_and_earlier_values() {
+        let vars = parse_str(
+            "HOST=localhost\nURL=http://${HOST}:3000\nCACHE=$HOME/cache\nMISSING=${NOPE}x\n",
+        );
+        assert_eq!(vars[1], pair("URL", "http://localhost:3000"));
+        assert_eq!(vars[2], pair("CACHE", "/home/dev/cache"));
+        assert_eq!(vars[3], pair("MISSING", "x"));
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        let real = |_: &str| None;
+        let content = "OK=1\nnot a variable\n1BAD=x\nOPEN=\"never closed\nJUNK='a' b\nAFTER=2\n";
+        let parsed = parse(content, &real, &mut BTreeMap::new());
+        assert_eq!(parsed.vars, vec![pair("OK", "1"), pair("AFTER", "2")]);
+        assert_eq!(parsed.skipped, vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn later_files_override_earlier_ones() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join(".env"), "A=base\nB=base\nC=base\n").unwrap();
+        fs::write(
+            temp.path().join(format!(".env.{}", rails_env())),
+            "B=env\nFROM_A=${A}\n",
+        )
+        .unwrap();
+        fs::write(temp.path().join(".env.local"), "C=local\n").unwrap();
+
+        let vars = load(temp.path()).unwrap();
+        let get = |key: &str| vars.iter().find(|v| v.key == key).unwrap();
+        assert_eq!(get("A").value, "base");
+        assert_eq!(get("FROM_A").value, "base");
+        assert!(get("A").source.ends_with(".env"));
+        if rails_env() != "test" {
+            assert_eq!(get("B").value, "env");
+            assert_eq!(get("C").value, "local");
+            assert!(get("C").source.ends_with(".env.local"));
+        }
+    }
+
+    #[test]
+    fn real_and_managed_variables_are_not_applied() {
+        let var = |key: &str, overridden| DotenvVar {
+            key: key.to_string(),
+            value: "from-file".to_string(),
+            source: PathBuf::from(".env"),
+            overridden,
+        };
+        let mut env_vars = HashMap::from([("GEM_HOME".to_string(), "/gems".to_string())]);
+        apply(
+            &mut env_vars,
+            &[
+                var("SECRET", false),
+                var("USER", true),
+                var("GEM_HOME", false),
+            ],
+        );
+        assert_eq!(env_vars["SECRET"], "from-file");
+        assert!(!env_vars.contains_key("USER"));
+        assert_eq!(env_vars["GEM_HOME"], "/gems");
+    }
+
+    #[test]
+    fn test_env_skips_local_file() {
+        let root = Path::new("/app");
+        assert_eq!(
+            files(root, "development"),
+            vec![
+                root.join(".env"),
+                root.join(".env.development"),
+                root.join(".env.local")
+            ]
+        );
+        assert_eq!(
+            files(root, "test"),
+            vec![root.join(".env"), root.join(".env.test")]
+        );
+    }
+}
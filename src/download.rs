@@ -1,61 +1,362 @@
 //! HTTP download functionality with progress bar
 //!
-//! Uses ureq for synchronous HTTP requests
+//! Uses ureq for synchronous HTTP requests. Releases come from GitHub unless
+//! a mirror is configured (`RAILSUP_RUBY_MIRROR` or `[download] mirror`);
+//! mirrors may be `file://` directories with the same layout.
 
+use crate::config::Config;
+use crate::util::lock::FileLock;
+use crate::util::ui;
 use crate::{paths, platform};
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use flate2::read::GzDecoder;
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::fs::{self, File};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, BufReader, Read, Write};
-use std::path::Path;
-use tar::Archive;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tar::{Archive, EntryType};
 
 const RUBY_RELEASES_URL: &str = "https://github.com/railsup-sh/ruby/releases/download";
 const GITHUB_API_RELEASES: &str = "https://api.github.com/repos/railsup-sh/ruby/releases";
 
+/// Minisign public keys trusted to sign each Ruby release's `.sha256` file
+/// (`ruby-<version>-<os>-<arch>.tar.gz.sha256.minisig`)
+///
+/// This is the railsup-sh/ruby release key. Its secret half exists only as
+/// the `MINISIGN_SECRET_KEY` Actions secret of railsup-sh/ruby, whose
+/// release workflow signs every `.sha256` it publishes. To rotate, add the
+/// new key here, ship a railsup release, then start signing with it; the
+/// old key is dropped once railsup versions that only know it are no
+/// longer supported.
+const RELEASE_PUBLIC_KEYS: &[&str] = &["RWRKDN7r+3scIXSJk4KuYZZYtjO6yboL57gnHMxEb4TPRrFPE26W8D+i"];
+
+/// Set by the global `--offline` flag
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Release index cache file (under cache_dir) and how long it stays fresh
+pub const RELEASE_INDEX_FILE: &str = "releases.json";
+const RELEASE_INDEX_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Upper bound on paginated release listing requests
+const MAX_INDEX_PAGES: usize = 10;
+
+/// Prefixes for in-flight install directories (skipped by `ruby list`)
+const STAGING_PREFIX: &str = ".staging-";
+const BACKUP_PREFIX: &str = ".backup-";
+
+/// Disable network access for the rest of this run
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Whether network access is disabled (`--offline` or `RAILSUP_OFFLINE=1`)
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+        || std::env::var("RAILSUP_OFFLINE")
+            .map(|v| matches!(v.trim(), "1" | "true" | "yes"))
+            .unwrap_or(false)
+}
+
+/// Where Ruby binaries and the version list come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReleaseSource {
+    /// GitHub releases of railsup-sh/ruby
+    GitHub,
+    /// A mirror with the same `v{version}/ruby-...` layout and an `index.json`
+    Mirror(String),
+}
+
+impl ReleaseSource {
+    /// Active source
+    /// Priority: RAILSUP_RUBY_MIRROR -> [download] mirror -> GitHub
+    pub fn current() -> Self {
+        let mirror = std::env::var("RAILSUP_RUBY_MIRROR")
+            .ok()
+            .filter(|m| !m.trim().is_empty())
+            .or_else(|| Config::load().ok().and_then(|c| c.download.mirror));
+        Self::from_mirror(mirror.as_deref())
+    }
+
+    fn from_mirror(mirror: Option<&str>) -> Self {
+        match mirror.map(|m| m.trim().trim_end_matches('/')) {
+            Some(m) if !m.is_empty() => ReleaseSource::Mirror(m.to_string()),
+            _ => ReleaseSource::GitHub,
+        }
+    }
+
+    /// URL of a release artifact for a version
+    pub fn artifact_url(&self, version: &str, filename: &str) -> String {
+        let base = match self {
+            ReleaseSource::GitHub => RUBY_RELEASES_URL,
+            ReleaseSource::Mirror(m) => m,
+        };
+        format!("{}/v{}/{}", base, version, filename)
+    }
+
+    /// URL of a version's Ruby tarball
+    pub fn tarball_url(&self, version: &str) -> String {
+        self.artifact_url(version, &cache_filename(version))
+    }
+
+    /// URL of a version's checksum file
+    pub fn checksum_url(&self, version: &str) -> String {
+        self.artifact_url(version, &format!("{}.sha256", cache_filename(version)))
+    }
+
+    /// URL of the version listing
+    fn index_url(&self) -> String {
+        match self {
+            ReleaseSource::GitHub => format!("{}?per_page=100", GITHUB_API_RELEASES),
+            ReleaseSource::Mirror(m) => format!("{}/index.json", m),
+        }
+    }
+}
+
+impl fmt::Display for ReleaseSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReleaseSource::GitHub => write!(f, "GitHub"),
+            ReleaseSource::Mirror(m) => write!(f, "{}", m),
+        }
+    }
+}
+
 /// Generate the download URL for a Ruby version
 pub fn ruby_download_url(version: &str) -> String {
-    let os = platform::detect_os();
-    let arch = platform::detect_arch();
-    format!(
-        "{}/v{}/ruby-{}-{}-{}.tar.gz",
-        RUBY_RELEASES_URL, version, version, os, arch
-    )
+    ReleaseSource::current().tarball_url(version)
 }
 
 /// Generate the checksum URL for a Ruby version
 pub fn checksum_url(version: &str) -> String {
-    let os = platform::detect_os();
-    let arch = platform::detect_arch();
-    format!(
-        "{}/v{}/ruby-{}-{}-{}.tar.gz.sha256",
-        RUBY_RELEASES_URL, version, version, os, arch
-    )
+    ReleaseSource::current().checksum_url(version)
 }
 
-/// Generate the cache filename for a Ruby version
-pub fn cache_filename(version: &str) -> String {
-    let os = platform::detect_os();
-    let arch = platform::detect_arch();
-    format!("ruby-{}-{}-{}.tar.gz", version, os, arch)
+/// Generate the signature URL for a Ruby version's checksum file
+pub fn signature_url(version: &str) -> String {
+    format!("{}.minisig", checksum_url(version))
+}
+
+/// Local path for a `file://` URL
+fn local_path(url: &str) -> Option<PathBuf> {
+    url.strip_prefix("file://").map(PathBuf::from)
 }
 
-/// Download a file with progress bar
-pub fn download_with_progress(url: &str, dest: &Path) -> Result<()> {
+/// Fetch a small text resource over HTTP or from a `file://` path
+pub fn fetch_text(url: &str) -> Result<String> {
+    if let Some(path) = local_path(url) {
+        return fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()));
+    }
+
     let response = ureq::get(url)
+        .set("User-Agent", "railsup")
         .call()
-        .with_context(|| format!("Failed to download: {}", url))?;
+        .with_context(|| format!("Failed to fetch: {}", url))?;
 
     if response.status() != 200 {
-        bail!("Failed to download: HTTP {}", response.status());
+        bail!("Failed to fetch {}: HTTP {}", url, response.status());
+    }
+
+    Ok(response.into_string()?)
+}
+
+/// Generate the cache filename for a Ruby version
+pub fn cache_filename(version: &str) -> String {
+    format!("ruby-{}-{}.tar.gz", version, platform::artifact_platform())
+}
+
+/// Every cache file that belongs to `version`: the prebuilt tarball and its
+/// checksum, plus source tarballs kept by `ruby build`
+pub fn version_cache_files(version: &str) -> [String; 4] {
+    let tarball = cache_filename(version);
+    [
+        format!("{}.sha256", tarball),
+        tarball,
+        format!("ruby-{}-source.tar.gz", version),
+        format!("ruby-{}-custom-source.tar.gz", version),
+    ]
+}
+
+/// The version a download cache file belongs to (see `version_cache_files`;
+/// `.part` files included)
+pub fn cache_file_version(name: &str) -> Option<String> {
+    let rest = name.strip_prefix("ruby-")?;
+    let platform = format!("-{}.tar.gz", platform::artifact_platform());
+    ["-custom-source.tar.gz", "-source.tar.gz", platform.as_str()]
+        .iter()
+        .find_map(|marker| rest.find(marker).map(|i| rest[..i].to_string()))
+        .filter(|version| !version.is_empty())
+}
+
+/// Path of the checksum stored next to a cached tarball (`<file>.sha256`)
+pub fn stored_checksum_path(tarball: &Path) -> PathBuf {
+    let mut name = tarball.as_os_str().to_os_string();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+/// Ruby versions with a tarball for this platform in the download cache
+pub fn cached_versions() -> Result<Vec<String>> {
+    let cache_dir = paths::cache_dir();
+    if !cache_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let suffix = format!("-{}.tar.gz", platform::artifact_platform());
+    let mut versions = Vec::new();
+    for entry in fs::read_dir(&cache_dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if let Some(version) = name
+            .strip_prefix("ruby-")
+            .and_then(|rest| rest.strip_suffix(&suffix))
+        {
+            versions.push(version.to_string());
+        }
+    }
+
+    versions.sort_by(|a, b| compare_versions(b, a));
+    Ok(versions)
+}
+
+/// How often and how patiently to retry a failed download
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry (doubles each time)
+    pub initial_delay: Duration,
+    /// Upper bound on the delay between attempts
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (1-based)
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// Path of the partial download for a destination file (`<file>.part`)
+pub fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_os_string();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// Why a single download attempt failed
+enum AttemptError {
+    /// Network hiccup or server error - worth trying again
+    Retryable(anyhow::Error),
+    /// Client error or local I/O failure - retrying won't help
+    Fatal(anyhow::Error),
+}
+
+/// Download a file with progress bar, resuming and retrying on failure
+///
+/// Bytes already present in `dest` are kept and resumed with an HTTP Range
+/// request, so callers should point this at a `.part` file.
+pub fn download_with_progress(url: &str, dest: &Path, policy: &RetryPolicy) -> Result<()> {
+    if let Some(path) = local_path(url) {
+        fs::copy(&path, dest).with_context(|| format!("Failed to copy {}", path.display()))?;
+        return Ok(());
+    }
+
+    let mut attempt = 1;
+    loop {
+        match download_attempt(url, dest) {
+            Ok(()) => return Ok(()),
+            Err(AttemptError::Fatal(e)) => return Err(e),
+            Err(AttemptError::Retryable(e)) => {
+                if attempt >= policy.max_attempts {
+                    return Err(e.context(format!("Download failed after {} attempts", attempt)));
+                }
+                let delay = policy.delay(attempt);
+                ui::warn(&format!(
+                    "Download interrupted ({:#}). Retrying in {:.1}s...",
+                    e,
+                    delay.as_secs_f32()
+                ));
+                thread::sleep(delay);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Make one request for `url`, appending to `dest` if the server honours Range
+fn download_attempt(url: &str, dest: &Path) -> std::result::Result<(), AttemptError> {
+    let existing = fs::metadata(dest).map(|m| m.len()).unwrap_or(0);
+
+    let mut request = ureq::get(url);
+    if existing > 0 {
+        request = request.set("Range", &format!("bytes={}-", existing));
     }
 
+    let response = match request.call() {
+        Ok(response) => response,
+        // Nothing left to fetch; the checksum decides if the file is good
+        Err(ureq::Error::Status(416, _)) if existing > 0 => return Ok(()),
+        Err(ureq::Error::Status(code, _)) if code == 429 || code >= 500 => {
+            return Err(AttemptError::Retryable(anyhow!(
+                "Failed to download: HTTP {}",
+                code
+            )))
+        }
+        Err(ureq::Error::Status(code, _)) => {
+            return Err(AttemptError::Fatal(anyhow!(
+                "Failed to download {}: HTTP {}",
+                url,
+                code
+            )))
+        }
+        Err(e) => {
+            return Err(AttemptError::Retryable(
+                anyhow::Error::new(e).context(format!("Failed to download: {}", url)),
+            ))
+        }
+    };
+
+    // 206 continues the partial file, 200 means the server sent everything again
+    let (file, start) = match response.status() {
+        206 => (OpenOptions::new().append(true).open(dest), existing),
+        200 => (File::create(dest), 0),
+        status => {
+            return Err(AttemptError::Fatal(anyhow!(
+                "Failed to download: HTTP {}",
+                status
+            )))
+        }
+    };
+    let mut file = file
+        .with_context(|| format!("Failed to create file: {}", dest.display()))
+        .map_err(AttemptError::Fatal)?;
+
     // Get content length for progress bar
     let content_length: u64 = response
         .header("Content-Length")
-        .and_then(|s| s.parse().ok())
+        .and_then(|s| s.parse::<u64>().ok())
+        .map(|len| len + start)
         .unwrap_or(0);
 
     // Create progress bar
@@ -78,50 +379,147 @@ pub fn download_with_progress(url: &str, dest: &Path) -> Result<()> {
         pb
     };
 
-    // Create destination file
-    let mut file =
-        File::create(dest).with_context(|| format!("Failed to create file: {}", dest.display()))?;
-
     // Read and write with progress updates
     let mut reader = response.into_reader();
     let mut buffer = [0u8; 8192];
-    let mut downloaded: u64 = 0;
+    let mut downloaded = start;
+    pb.set_position(downloaded);
 
     loop {
-        let bytes_read = reader.read(&mut buffer)?;
+        let bytes_read = match reader.read(&mut buffer) {
+            Ok(n) => n,
+            Err(e) => {
+                pb.abandon();
+                return Err(AttemptError::Retryable(e.into()));
+            }
+        };
         if bytes_read == 0 {
             break;
         }
 
-        file.write_all(&buffer[..bytes_read])?;
+        file.write_all(&buffer[..bytes_read])
+            .with_context(|| format!("Failed to write file: {}", dest.display()))
+            .map_err(AttemptError::Fatal)?;
         downloaded += bytes_read as u64;
         pb.set_position(downloaded);
     }
 
+    if content_length > 0 && downloaded < content_length {
+        pb.abandon();
+        return Err(AttemptError::Retryable(anyhow!(
+            "connection closed after {} of {} bytes",
+            downloaded,
+            content_length
+        )));
+    }
+
     pb.finish_with_message("Download complete");
     Ok(())
 }
 
-/// Download checksum and verify a file
-pub fn verify_checksum(file_path: &Path, version: &str) -> Result<bool> {
-    // Download checksum
+/// Download the published SHA-256 checksum for a Ruby tarball
+///
+/// With `verify_signatures` on, the checksum file must be signed with a
+/// release key, so a tampered release can't simply ship a matching checksum
+/// for a modified tarball.
+fn fetch_verified_checksum(version: &str, filename: &str) -> Result<String> {
     let url = checksum_url(version);
-    let response = ureq::get(&url)
-        .call()
-        .with_context(|| format!("Failed to download checksum: {}", url))?;
+    let checksum_content = fetch_text(&url).context("Failed to download checksum")?;
+    verify_release_signature(version, &checksum_content)?;
+    parse_checksum_for(&checksum_content, filename)
+}
 
-    if response.status() != 200 {
-        bail!("Failed to download checksum: HTTP {}", response.status());
+/// Whether release signatures must be checked
+///
+/// Always for GitHub releases. A custom mirror without `.minisig` files may
+/// opt out.
+/// Priority: RAILSUP_VERIFY_SIGNATURES -> [download] verify_signatures -> true
+fn signatures_required() -> bool {
+    if ReleaseSource::current() == ReleaseSource::GitHub {
+        return true;
+    }
+    if let Ok(value) = std::env::var("RAILSUP_VERIFY_SIGNATURES") {
+        match value.trim() {
+            "1" | "true" | "yes" => return true,
+            "0" | "false" | "no" => return false,
+            _ => {}
+        }
+    }
+    Config::load()
+        .ok()
+        .and_then(|c| c.download.verify_signatures)
+        .unwrap_or(true)
+}
+
+/// Check the `.minisig` signature of a release checksum file
+fn verify_release_signature(version: &str, checksum_content: &str) -> Result<()> {
+    if !signatures_required() {
+        return Ok(());
+    }
+
+    ui::progress("Verifying signature...");
+    let url = signature_url(version);
+    let signature = fetch_text(&url).with_context(|| {
+        format!(
+            "Failed to download release signature for Ruby {}.\n\
+             If you use a mirror without .minisig files, set verify_signatures = false \
+             under [download] in ~/.railsup/config.toml (or RAILSUP_VERIFY_SIGNATURES=0)",
+            version
+        )
+    })?;
+
+    // Any trusted key will do, so releases keep verifying across a rotation
+    let mut result = Err(anyhow!("No release keys configured"));
+    for key in RELEASE_PUBLIC_KEYS {
+        result = verify_signature(checksum_content.as_bytes(), &signature, key);
+        if result.is_ok() {
+            break;
+        }
     }
+    result.with_context(|| {
+        format!(
+            "Ruby {} checksum is not signed by the railsup release key. \
+             The release may have been tampered with.",
+            version
+        )
+    })
+}
+
+/// Verify a minisign signature over `content` with a base64 public key
+fn verify_signature(content: &[u8], signature: &str, public_key: &str) -> Result<()> {
+    let key = minisign_verify::PublicKey::from_base64(public_key)
+        .map_err(|e| anyhow!("Invalid public key: {}", e))?;
+    let signature = minisign_verify::Signature::decode(signature)
+        .map_err(|e| anyhow!("Malformed signature file: {}", e))?;
+    key.verify(content, &signature, false)
+        .map_err(|e| anyhow!("Signature verification failed: {}", e))
+}
 
-    let checksum_content = response.into_string()?;
-    let expected = checksum_content
+/// Extract the hex digest from `<sha256>  <filename>` content
+fn parse_checksum(content: &str) -> Result<String> {
+    Ok(content
         .split_whitespace()
         .next()
         .context("Invalid checksum file format")?
-        .to_lowercase();
+        .to_lowercase())
+}
+
+/// Like `parse_checksum`, but reject a checksum that names a different file
+///
+/// Stops a validly signed checksum for one artifact being replayed for another.
+fn parse_checksum_for(content: &str, filename: &str) -> Result<String> {
+    let expected = parse_checksum(content)?;
+    if let Some(named) = content.split_whitespace().nth(1) {
+        let named = named.trim_start_matches('*');
+        if named != filename {
+            bail!("Checksum file is for {}, expected {}", named, filename);
+        }
+    }
+    Ok(expected)
+}
 
-    // Calculate actual checksum
+/// Calculate the SHA-256 of a file
+pub fn file_sha256(file_path: &Path) -> Result<String> {
     let file = File::open(file_path)
         .with_context(|| format!("Failed to open file for checksum: {}", file_path.display()))?;
     let mut reader = BufReader::new(file);
@@ -129,19 +527,57 @@ pub fn verify_checksum(file_path: &Path, version: &str) -> Result<bool> {
 
     io::copy(&mut reader, &mut hasher)?;
 
-    let actual = format!("{:x}", hasher.finalize());
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Result of checking a cached tarball
+#[derive(Debug, PartialEq, Eq)]
+enum CachedTarball {
+    /// Matches the checksum stored when it was downloaded
+    Valid,
+    /// Doesn't match its stored checksum
+    Corrupt,
+    /// No stored checksum to compare against
+    Unverified,
+}
+
+/// Check a cached tarball against the checksum stored when it was downloaded
+///
+/// Older railsup versions downloaded straight into the cache and stored no
+/// checksum, so an interrupted download could leave a truncated tarball
+/// there. Such files are `Unverified` until checked against the published
+/// checksum.
+fn check_cached_tarball(tarball: &Path) -> Result<CachedTarball> {
+    let checksum_path = stored_checksum_path(tarball);
+    if !checksum_path.exists() {
+        return Ok(CachedTarball::Unverified);
+    }
+    let expected = parse_checksum(&fs::read_to_string(&checksum_path)?)?;
+    if file_sha256(tarball)? == expected {
+        Ok(CachedTarball::Valid)
+    } else {
+        Ok(CachedTarball::Corrupt)
+    }
+}
 
-    Ok(actual == expected)
+/// Keep a verified checksum next to a cached tarball for later runs
+fn store_checksum(tarball: &Path, checksum: &str, filename: &str) -> Result<()> {
+    let checksum_path = stored_checksum_path(tarball);
+    fs::write(&checksum_path, format!("{}  {}\n", checksum, filename))
+        .with_context(|| format!("Failed to write {}", checksum_path.display()))
 }
 
 /// Fix shebangs in Ruby bin scripts to point to the correct ruby path
-fn fix_shebangs(ruby_dir: &Path) -> Result<()> {
+///
+/// Scripts are rewritten in `ruby_dir` (the staging copy) but point at
+/// `install_dir`, where the tree will live once moved into place.
+fn fix_shebangs(ruby_dir: &Path, install_dir: &Path) -> Result<()> {
     let bin_dir = ruby_dir.join("bin");
     if !bin_dir.exists() {
         return Ok(());
     }
 
-    let ruby_path = bin_dir.join("ruby");
+    let ruby_path = install_dir.join("bin").join("ruby");
     let new_shebang = format!("#!{}\n", ruby_path.display());
 
     for entry in fs::read_dir(&bin_dir)? {
@@ -173,7 +609,13 @@ fn fix_shebangs(ruby_dir: &Path) -> Result<()> {
 }
 
 /// Extract a tarball to a destination directory
-pub fn extract_tarball(tarball: &Path, dest_dir: &Path) -> Result<()> {
+///
+/// Every entry must live under `root/` (e.g. `ruby-4.0.1/`): absolute paths,
+/// `..` components, device files and links that point outside `root` are
+/// rejected before anything is written for them. So are entries and link
+/// targets that go through a symlink extracted earlier, which could
+/// otherwise chain links to escape `root`.
+pub fn extract_tarball(tarball: &Path, dest_dir: &Path, root: &str) -> Result<()> {
     let file = File::open(tarball)
         .with_context(|| format!("Failed to open tarball: {}", tarball.display()))?;
 
@@ -183,43 +625,432 @@ pub fn extract_tarball(tarball: &Path, dest_dir: &Path) -> Result<()> {
     // Create destination directory
     fs::create_dir_all(dest_dir)?;
 
-    // Extract to destination
-    archive
-        .unpack(dest_dir)
-        .with_context(|| format!("Failed to extract tarball to: {}", dest_dir.display()))?;
+    let mut links = ExtractedLinks::default();
+    let mut extracted = 0;
+    for entry in archive
+        .entries()
+        .with_context(|| format!("Failed to read tarball: {}", tarball.display()))?
+    {
+        let mut entry = entry.context("Failed to read tarball entry")?;
+        let path = entry.path()?.into_owned();
+        validate_entry_path(&path, root)?;
+        links.check_path(&path)?;
+
+        match entry.header().entry_type() {
+            EntryType::Symlink => {
+                let target = entry
+                    .link_name()?
+                    .with_context(|| format!("Symlink {} has no target", path.display()))?;
+                validate_symlink_target(&path, &target, root)?;
+                links.add_symlink(&path, &target)?;
+            }
+            EntryType::Link => {
+                let target = entry
+                    .link_name()?
+                    .with_context(|| format!("Hard link {} has no target", path.display()))?;
+                validate_entry_path(&target, root).with_context(|| {
+                    format!("Hard link {} points outside {}/", path.display(), root)
+                })?;
+                links.check_path(&target)?;
+            }
+            EntryType::Char | EntryType::Block | EntryType::Fifo => {
+                bail!(
+                    "Refusing to extract {}: device and FIFO entries are not allowed",
+                    path.display()
+                );
+            }
+            _ => {}
+        }
+
+        // Extract to destination
+        if !entry
+            .unpack_in(dest_dir)
+            .with_context(|| format!("Failed to extract {}", path.display()))?
+        {
+            bail!(
+                "Refusing to extract {}: escapes the install directory",
+                path.display()
+            );
+        }
+        extracted += 1;
+    }
+
+    if extracted == 0 || !dest_dir.join(root).is_dir() {
+        bail!("Tarball does not contain the expected {}/ directory", root);
+    }
+
+    Ok(())
+}
+
+/// Check that an archive path is relative and sits under `root/`
+fn validate_entry_path(path: &Path, root: &str) -> Result<()> {
+    let mut components = path
+        .components()
+        .skip_while(|c| matches!(c, Component::CurDir))
+        .peekable();
+
+    match components.next() {
+        Some(Component::Normal(first)) if first == root => {}
+        Some(Component::RootDir) | Some(Component::Prefix(_)) => {
+            bail!("Refusing to extract {}: absolute path", path.display())
+        }
+        _ => bail!(
+            "Refusing to extract {}: expected everything under {}/",
+            path.display(),
+            root
+        ),
+    }
+
+    for component in components {
+        match component {
+            Component::Normal(_) | Component::CurDir => {}
+            Component::ParentDir => {
+                bail!("Refusing to extract {}: contains '..'", path.display())
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                bail!("Refusing to extract {}: absolute path", path.display())
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that a symlink at `path` resolves to somewhere inside `root/`
+fn validate_symlink_target(path: &Path, target: &Path, root: &str) -> Result<()> {
+    // Directory depth below root where the link lives
+    let mut depth = path
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .count()
+        .saturating_sub(2);
+
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if depth == 0 {
+                    bail!(
+                        "Refusing to extract {}: symlink to {} points outside {}/",
+                        path.display(),
+                        target.display(),
+                        root
+                    );
+                }
+                depth -= 1;
+            }
+            Component::RootDir | Component::Prefix(_) => bail!(
+                "Refusing to extract {}: symlink to absolute path {}",
+                path.display(),
+                target.display()
+            ),
+        }
+    }
 
     Ok(())
 }
 
+/// Symlinks extracted so far, checked lexically so later entries can't be
+/// routed through them
+#[derive(Default)]
+struct ExtractedLinks {
+    /// Paths of the symlink entries
+    links: HashSet<PathBuf>,
+    /// Directories that earlier symlink targets resolve through
+    traversed: HashSet<PathBuf>,
+}
+
+impl ExtractedLinks {
+    /// Reject a path whose parent directories include an extracted symlink
+    fn check_path(&self, path: &Path) -> Result<()> {
+        let path = normal_path(path);
+        for parent in path.ancestors().skip(1) {
+            if self.links.contains(parent) {
+                bail!(
+                    "Refusing to extract {}: goes through symlink {}",
+                    path.display(),
+                    parent.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Record a symlink whose path passed `check_path`
+    ///
+    /// Its target may not resolve through an extracted symlink, and it may
+    /// not replace a directory that an earlier target resolves through.
+    fn add_symlink(&mut self, path: &Path, target: &Path) -> Result<()> {
+        let path = normal_path(path);
+        if self.traversed.contains(&path) {
+            bail!(
+                "Refusing to extract {}: an earlier symlink resolves through it",
+                path.display()
+            );
+        }
+
+        let mut resolved = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let mut components = target.components().peekable();
+        while let Some(component) = components.next() {
+            match component {
+                Component::Normal(name) => resolved.push(name),
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                _ => {}
+            }
+            if components.peek().is_none() {
+                break;
+            }
+            if self.links.contains(&resolved) {
+                bail!(
+                    "Refusing to extract {}: symlink to {} goes through symlink {}",
+                    path.display(),
+                    target.display(),
+                    resolved.display()
+                );
+            }
+            self.traversed.insert(resolved.clone());
+        }
+
+        self.links.insert(path);
+        Ok(())
+    }
+}
+
+/// An archive path without `.` components, for comparing entries
+fn normal_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect()
+}
+
 /// Fetch available Ruby versions from GitHub releases
+///
+/// In offline mode this lists the versions in the download cache instead.
 pub fn fetch_available_versions() -> Result<Vec<String>> {
-    let response = ureq::get(GITHUB_API_RELEASES)
-        .set("User-Agent", "railsup")
-        .call()
-        .context("Failed to fetch releases from GitHub")?;
+    available_versions(false)
+}
 
-    if response.status() != 200 {
-        bail!("Failed to fetch releases: HTTP {}", response.status());
+/// Release list persisted between runs
+#[derive(Debug, Serialize, Deserialize)]
+struct ReleaseIndexCache {
+    /// Index URL the versions came from (a mirror change invalidates it)
+    source: String,
+    /// Unix timestamp of the last successful check
+    fetched_at: u64,
+    etag: Option<String>,
+    versions: Vec<String>,
+}
+
+/// Result of a (possibly conditional) release index request
+enum IndexFetch {
+    NotModified,
+    Fetched {
+        versions: Vec<String>,
+        etag: Option<String>,
+    },
+}
+
+/// List available Ruby versions, newest first
+///
+/// The list is cached in `releases.json` for an hour and revalidated with
+/// ETag/If-None-Match after that. `refresh` skips the TTL. When the network
+/// fails, a stale cache is used rather than nothing.
+pub fn available_versions(refresh: bool) -> Result<Vec<String>> {
+    if is_offline() {
+        return cached_versions();
     }
 
-    let body = response.into_string()?;
-    let releases: Vec<serde_json::Value> =
-        serde_json::from_str(&body).context("Failed to parse GitHub releases response")?;
+    let source = ReleaseSource::current();
+    let index_url = source.index_url();
+    let cache_path = paths::cache_dir().join(RELEASE_INDEX_FILE);
+    let cached = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<ReleaseIndexCache>(&content).ok())
+        .filter(|cache| cache.source == index_url);
+    let now = unix_now();
 
-    let mut versions: Vec<String> = releases
-        .iter()
-        .filter_map(|r| r.get("tag_name"))
-        .filter_map(|t| t.as_str())
-        .map(|t| t.trim_start_matches('v').to_string())
-        .collect();
+    if let Some(cache) = &cached {
+        let age = Duration::from_secs(now.saturating_sub(cache.fetched_at));
+        if !refresh && age < RELEASE_INDEX_TTL {
+            return Ok(sorted_versions(cache.versions.clone()));
+        }
+    }
+
+    // Only GitHub gets the token; never leak it to a mirror
+    let token = match source {
+        ReleaseSource::GitHub => std::env::var("GITHUB_TOKEN")
+            .ok()
+            .filter(|t| !t.trim().is_empty()),
+        ReleaseSource::Mirror(_) => None,
+    };
+    let etag = cached.as_ref().and_then(|c| c.etag.as_deref());
 
+    let cache = match (
+        fetch_release_index(&index_url, etag, token.as_deref()),
+        cached,
+    ) {
+        (Ok(IndexFetch::NotModified), Some(mut cache)) => {
+            cache.fetched_at = now;
+            cache
+        }
+        (Ok(IndexFetch::NotModified), None) => {
+            bail!("Release index returned 304 Not Modified without a cached copy")
+        }
+        (Ok(IndexFetch::Fetched { versions, etag }), _) => ReleaseIndexCache {
+            source: index_url,
+            fetched_at: now,
+            etag,
+            versions,
+        },
+        (Err(_), Some(cache)) if !refresh => return Ok(sorted_versions(cache.versions)),
+        (Err(e), _) => {
+            return Err(e).with_context(|| format!("Failed to fetch releases from {}", source))
+        }
+    };
+
+    // Best effort: a read-only cache dir shouldn't break listing
+    if let Ok(content) = serde_json::to_string_pretty(&cache) {
+        let _ = fs::create_dir_all(paths::cache_dir());
+        let _ = fs::write(&cache_path, content);
+    }
+
+    Ok(sorted_versions(cache.versions))
+}
+
+/// Request the release index, following `Link: rel="next"` pagination
+fn fetch_release_index(url: &str, etag: Option<&str>, token: Option<&str>) -> Result<IndexFetch> {
+    if local_path(url).is_some() {
+        let versions = parse_release_index(&fetch_text(url)?)?;
+        return Ok(IndexFetch::Fetched {
+            versions,
+            etag: None,
+        });
+    }
+
+    let mut versions = Vec::new();
+    let mut first_etag = None;
+    let mut next = Some(url.to_string());
+
+    for page in 0..MAX_INDEX_PAGES {
+        let Some(page_url) = next.take() else {
+            break;
+        };
+
+        let mut request = ureq::get(&page_url).set("User-Agent", "railsup");
+        if let Some(token) = token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        if page == 0 {
+            if let Some(etag) = etag {
+                request = request.set("If-None-Match", etag);
+            }
+        }
+
+        let response = request
+            .call()
+            .with_context(|| format!("Failed to fetch: {}", page_url))?;
+
+        if page == 0 && response.status() == 304 {
+            return Ok(IndexFetch::NotModified);
+        }
+        if response.status() != 200 {
+            bail!("Failed to fetch releases: HTTP {}", response.status());
+        }
+
+        if page == 0 {
+            first_etag = response.header("ETag").map(|e| e.to_string());
+        }
+        next = response.header("Link").and_then(parse_next_link);
+
+        let body = response.into_string()?;
+        versions.extend(parse_release_index(&body)?);
+    }
+
+    Ok(IndexFetch::Fetched {
+        versions,
+        etag: first_etag,
+    })
+}
+
+/// Extract the `rel="next"` URL from a Link header
+fn parse_next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == "rel=\"next\"")
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+fn sorted_versions(mut versions: Vec<String>) -> Vec<String> {
     // Sort by version (newest first)
     versions.sort_by(|a, b| compare_versions(b, a));
+    versions.dedup();
+    versions
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Parse a release listing into version strings
+///
+/// Accepts the GitHub releases API response (`[{"tag_name": "v3.4.1"}]`),
+/// a plain JSON array (`["3.4.1"]`), `{"versions": [...]}`, or a text file
+/// with one version per line.
+fn parse_release_index(body: &str) -> Result<Vec<String>> {
+    fn from_json(value: &serde_json::Value) -> Option<Vec<String>> {
+        let items = match value {
+            serde_json::Value::Array(items) => items,
+            serde_json::Value::Object(map) => map.get("versions")?.as_array()?,
+            _ => return None,
+        };
+        Some(
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    serde_json::Value::String(s) => Some(s.as_str()),
+                    _ => item
+                        .get("tag_name")
+                        .or_else(|| item.get("version"))
+                        .and_then(|t| t.as_str()),
+                })
+                .map(|t| t.trim_start_matches('v').to_string())
+                .collect(),
+        )
+    }
+
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(body) {
+        return from_json(&value).context("Unrecognized release index format");
+    }
+
+    let versions: Vec<String> = body
+        .lines()
+        .map(|l| l.trim().trim_start_matches('v'))
+        .filter(|l| l.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|l| l.to_string())
+        .collect();
+
+    if versions.is_empty() {
+        bail!("Release index contains no versions");
+    }
     Ok(versions)
 }
 
 /// Compare two version strings (simple semver comparison)
-fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
     let a_parts: Vec<u32> = a.split('.').filter_map(|p| p.parse().ok()).collect();
     let b_parts: Vec<u32> = b.split('.').filter_map(|p| p.parse().ok()).collect();
 
@@ -253,7 +1084,15 @@ pub fn find_latest_in_series(series: &str, available: &[String]) -> Option<Strin
 
 /// Check if a version is available
 pub fn is_version_available(version: &str) -> Result<bool> {
+    if is_offline() {
+        return Ok(paths::cache_dir().join(cache_filename(version)).exists());
+    }
+
     let url = ruby_download_url(version);
+    if let Some(path) = local_path(&url) {
+        return Ok(path.is_file());
+    }
+
     let response = ureq::head(&url).call();
 
     match response {
@@ -263,58 +1102,313 @@ pub fn is_version_available(version: &str) -> Result<bool> {
     }
 }
 
-/// Download and install a Ruby version
-pub fn download_ruby(version: &str, force: bool) -> Result<()> {
-    let dest = paths::ruby_version_dir(version);
-
-    // Check if already installed
-    if dest.exists() && !force {
-        println!(
-            "Ruby {} is already installed at {}",
+/// Fail with an explanation when a musl build of `version` isn't published
+///
+/// musl builds trail the glibc ones, so a version can exist upstream while
+/// the `linux-musl-*` artifact doesn't.
+fn ensure_platform_build(version: &str) -> Result<()> {
+    if platform::detect_libc() != Some(platform::Libc::Musl) {
+        return Ok(());
+    }
+    if let Ok(false) = is_version_available(version) {
+        bail!(
+            "No musl build of Ruby {} is published for {} (looked for {}).\n\
+             glibc builds won't run on musl systems such as Alpine. Pick another version \
+             (railsup ruby list --available) or use a glibc-based image like debian-slim.",
             version,
-            dest.display()
+            platform::artifact_platform(),
+            cache_filename(version)
         );
-        return Ok(());
     }
+    Ok(())
+}
 
-    // Ensure directories exist
-    paths::ensure_dirs()?;
+/// Install an extracted Ruby into `dest` without ever exposing a partial tree
+///
+/// The tarball is unpacked into `.staging-<name>-<pid>` next to `dest`,
+/// shebangs are fixed, and `bin/ruby -v` must succeed before the staged tree
+/// is renamed into place. An existing install is parked as
+/// `.backup-<name>-<pid>` and restored if the swap fails.
+pub fn install_tarball(tarball: &Path, dest: &Path) -> Result<()> {
+    install_staged(dest, |staging, name| {
+        // The tarball extracts to a directory named ruby-{version}
+        extract_tarball(tarball, staging, name)?;
+        Ok(staging.join(name))
+    })
+}
 
-    // Cache path
+/// Install a Ruby tree produced by `populate` into `dest`
+///
+/// `populate` receives the staging directory and the install's directory
+/// name, fills it, and returns where the Ruby tree ended up. The tree then
+/// goes through the same checks and swap as a binary install.
+pub fn install_staged<F>(dest: &Path, populate: F) -> Result<()>
+where
+    F: FnOnce(&Path, &str) -> Result<PathBuf>,
+{
+    let parent = dest.parent().expect("Ruby dir should have parent");
+    let name = dest
+        .file_name()
+        .expect("Ruby dir should have a name")
+        .to_string_lossy()
+        .to_string();
+    let pid = std::process::id();
+    let staging = parent.join(format!("{}{}-{}", STAGING_PREFIX, name, pid));
+    let backup = parent.join(format!("{}{}-{}", BACKUP_PREFIX, name, pid));
+
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    let result = populate(&staging, &name)
+        .and_then(|staged| verify_staged(&staged, &name, dest))
+        .and_then(|staged| swap_into_place(&staged, dest, &backup));
+
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+/// Validate a staged Ruby tree and fix its shebangs, returning its path
+fn verify_staged(staged: &Path, name: &str, dest: &Path) -> Result<PathBuf> {
+    let ruby = staged.join("bin").join("ruby");
+    if !ruby.is_file() {
+        bail!("Ruby archive is missing {}/bin/ruby", name);
+    }
+
+    // Fix shebangs to point to the installed ruby path
+    fix_shebangs(staged, dest)?;
+
+    // Make sure the interpreter actually runs on this machine
+    let output = Command::new(&ruby)
+        .arg("-v")
+        .output()
+        .with_context(|| format!("Failed to run {}", ruby.display()))?;
+    if !output.status.success() {
+        bail!(
+            "Installed Ruby failed to run (bin/ruby -v exited with {}):\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(staged.to_path_buf())
+}
+
+/// Rename `staged` to `dest`, keeping any previous install recoverable
+fn swap_into_place(staged: &Path, dest: &Path, backup: &Path) -> Result<()> {
+    let had_previous = dest.exists();
+    if had_previous {
+        fs::rename(dest, backup)
+            .with_context(|| format!("Failed to move aside {}", dest.display()))?;
+    }
+
+    if let Err(e) = fs::rename(staged, dest) {
+        if had_previous {
+            fs::rename(backup, dest).with_context(|| {
+                format!(
+                    "Failed to restore previous install from {}",
+                    backup.display()
+                )
+            })?;
+        }
+        return Err(e).with_context(|| format!("Failed to move Ruby into {}", dest.display()));
+    }
+
+    if had_previous {
+        fs::remove_dir_all(backup)?;
+    }
+    Ok(())
+}
+
+/// Remove staging directories and restore backups left by interrupted installs
+///
+/// Installs run under their version's install lock, so leftovers of a
+/// version whose lock is free belong to an install that is gone. The pid in
+/// the name can't tell that: it may be reused, or live in another PID
+/// namespace sharing this home. Versions being installed are left alone.
+pub fn cleanup_stale_installs(ruby_root: &Path) -> Result<()> {
+    cleanup_stale_installs_at(ruby_root, |version| {
+        paths::lock_file(&install_lock_name(version))
+    })
+}
+
+fn cleanup_stale_installs_at<F>(ruby_root: &Path, lock_file: F) -> Result<()>
+where
+    F: Fn(&str) -> PathBuf,
+{
+    if !ruby_root.exists() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(ruby_root)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+
+        let (rest, is_backup) = if let Some(rest) = file_name.strip_prefix(STAGING_PREFIX) {
+            (rest, false)
+        } else if let Some(rest) = file_name.strip_prefix(BACKUP_PREFIX) {
+            (rest, true)
+        } else {
+            continue;
+        };
+
+        let Some((name, _pid)) = rest.rsplit_once('-') else {
+            continue;
+        };
+        let version = name.strip_prefix("ruby-").unwrap_or(name);
+        let Some(_lock) = FileLock::try_acquire_at(&lock_file(version))? else {
+            continue;
+        };
+
+        let path = entry.path();
+        let dest = ruby_root.join(name);
+        if is_backup && !dest.exists() {
+            // Interrupted mid-swap: put the previous install back
+            fs::rename(&path, &dest)?;
+        } else {
+            fs::remove_dir_all(&path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Hold the per-version install lock (shared by downloads and source builds)
+pub fn install_lock(version: &str) -> Result<FileLock> {
+    FileLock::acquire(
+        &install_lock_name(version),
+        &format!("installing Ruby {}", version),
+    )
+}
+
+/// Take the per-version install lock unless another process holds it
+pub fn try_install_lock(version: &str) -> Result<Option<FileLock>> {
+    FileLock::try_acquire(&install_lock_name(version))
+}
+
+fn install_lock_name(version: &str) -> String {
+    format!("ruby-{}", version)
+}
+
+/// Download and install a Ruby version
+pub fn download_ruby(version: &str, force: bool) -> Result<()> {
+    let dest = paths::ruby_version_dir(version);
+
+    // Finish or roll back installs interrupted by a previous run
+    let _ = cleanup_stale_installs(&paths::ruby_dir());
+
+    // Another railsup may be installing the same version; once it finishes
+    // the check below finds its install
+    let _lock = install_lock(version)?;
+
+    // Check if already installed
+    if dest.exists() && !force {
+        ui::progress(&format!(
+            "Ruby {} is already installed at {}",
+            version,
+            dest.display()
+        ));
+        return Ok(());
+    }
+
+    // Refuse early rather than install binaries that can't load
+    platform::check_compatible()?;
+
+    // Ensure directories exist
+    paths::ensure_dirs()?;
+
+    // Cache path
     let filename = cache_filename(version);
     let cache_path = paths::cache_dir().join(&filename);
 
-    // Download if not cached
+    // Drop a cached tarball that no longer matches its checksum
+    let checksum_path = stored_checksum_path(&cache_path);
+    let discard = if !cache_path.exists() {
+        false
+    } else {
+        match check_cached_tarball(&cache_path)? {
+            CachedTarball::Valid => false,
+            CachedTarball::Corrupt => true,
+            CachedTarball::Unverified if is_offline() => {
+                ui::warn(&format!(
+                    "Cached {} has no stored checksum and can't be verified offline; \
+                     using it unverified",
+                    filename
+                ));
+                false
+            }
+            CachedTarball::Unverified => {
+                ui::progress(&format!("Verifying cached {}...", filename));
+                let expected = fetch_verified_checksum(version, &filename)?;
+                let matches = file_sha256(&cache_path)? == expected;
+                if matches {
+                    store_checksum(&cache_path, &expected, &filename)?;
+                }
+                !matches
+            }
+        }
+    };
+    if discard {
+        ui::warn(&format!(
+            "Cached {} failed checksum verification, discarding it",
+            filename
+        ));
+        fs::remove_file(&cache_path)?;
+        let _ = fs::remove_file(&checksum_path);
+    }
+
+    // Download if not cached. Bytes land in a .part file that only becomes
+    // the cached tarball once the checksum passes.
     if !cache_path.exists() {
+        if is_offline() {
+            let cached = cached_versions().unwrap_or_default();
+            let hint = if cached.is_empty() {
+                "The download cache is empty.".to_string()
+            } else {
+                format!("Cached versions: {}", cached.join(", "))
+            };
+            bail!(
+                "Ruby {} is not in the download cache and offline mode is on.\n{}",
+                version,
+                hint
+            );
+        }
+
+        ensure_platform_build(version)?;
+
         let url = ruby_download_url(version);
-        println!("Downloading {}...", filename);
-        download_with_progress(&url, &cache_path)?;
+        let part = part_path(&cache_path);
+        if part.exists() {
+            ui::progress(&format!("Resuming download of {}...", filename));
+        } else {
+            ui::progress(&format!("Downloading {}...", filename));
+        }
+        download_with_progress(&url, &part, &RetryPolicy::default())?;
 
         // Verify checksum
-        println!("Verifying checksum...");
-        if !verify_checksum(&cache_path, version)? {
-            fs::remove_file(&cache_path)?;
+        ui::progress("Verifying checksum...");
+        let expected = fetch_verified_checksum(version, &filename)?;
+        if file_sha256(&part)? != expected {
+            fs::remove_file(&part)?;
             bail!("Checksum verification failed. The download may be corrupted.");
         }
-    } else {
-        println!("Using cached {}...", filename);
-    }
 
-    // Remove existing installation if force
-    if dest.exists() && force {
-        fs::remove_dir_all(&dest)?;
+        // Keep the checksum so the cache can be reused (and re-verified) offline
+        store_checksum(&cache_path, &expected, &filename)?;
+        fs::rename(&part, &cache_path).with_context(|| {
+            format!(
+                "Failed to move download into cache: {}",
+                cache_path.display()
+            )
+        })?;
+    } else {
+        ui::progress(&format!("Using cached {}...", filename));
     }
 
-    // Extract
-    println!("Extracting to {}...", dest.display());
-
-    // The tarball extracts to a directory named ruby-{version}
-    // We need to extract to parent and then we're done
-    let parent = dest.parent().expect("Ruby dir should have parent");
-    extract_tarball(&cache_path, parent)?;
-
-    // Fix shebangs to point to the installed ruby path
-    fix_shebangs(&dest)?;
+    // Extract into a staging directory and move it into place atomically
+    ui::progress(&format!("Extracting to {}...", dest.display()));
+    install_tarball(&cache_path, &dest)?;
 
     // Create gems directory for this version
     let gems_dir = paths::gems_version_dir(version);
@@ -329,7 +1423,7 @@ mod tests {
 
     #[test]
     fn ruby_download_url_format() {
-        let url = ruby_download_url("4.0.1");
+        let url = ReleaseSource::GitHub.tarball_url("4.0.1");
         assert!(url.contains("github.com/railsup-sh/ruby/releases"));
         assert!(url.contains("v4.0.1"));
         assert!(url.contains("ruby-4.0.1"));
@@ -338,7 +1432,7 @@ mod tests {
 
     #[test]
     fn checksum_url_format() {
-        let url = checksum_url("4.0.1");
+        let url = ReleaseSource::GitHub.checksum_url("4.0.1");
         assert!(url.contains("github.com/railsup-sh/ruby/releases"));
         assert!(url.ends_with(".sha256"));
     }
@@ -349,4 +1443,675 @@ mod tests {
         assert!(filename.starts_with("ruby-4.0.1"));
         assert!(filename.ends_with(".tar.gz"));
     }
+
+    // ==================== mirror tests ====================
+
+    #[test]
+    fn mirror_artifact_url_uses_release_layout() {
+        let source = ReleaseSource::from_mirror(Some("https://artifacts.corp/railsup-ruby/"));
+        assert_eq!(
+            source.artifact_url("4.0.1", "ruby-4.0.1-linux-x86_64.tar.gz"),
+            "https://artifacts.corp/railsup-ruby/v4.0.1/ruby-4.0.1-linux-x86_64.tar.gz"
+        );
+        assert_eq!(
+            source.index_url(),
+            "https://artifacts.corp/railsup-ruby/index.json"
+        );
+        assert_eq!(
+            ReleaseSource::from_mirror(Some("  ")),
+            ReleaseSource::GitHub
+        );
+        assert_eq!(ReleaseSource::from_mirror(None), ReleaseSource::GitHub);
+    }
+
+    #[test]
+    fn parse_release_index_formats() {
+        let github = r#"[{"tag_name": "v4.0.1"}, {"tag_name": "v3.4.7"}]"#;
+        assert_eq!(parse_release_index(github).unwrap(), vec!["4.0.1", "3.4.7"]);
+
+        let plain = r#"["4.0.1", "3.4.7"]"#;
+        assert_eq!(parse_release_index(plain).unwrap(), vec!["4.0.1", "3.4.7"]);
+
+        let object = r#"{"versions": ["3.3.9"]}"#;
+        assert_eq!(parse_release_index(object).unwrap(), vec!["3.3.9"]);
+
+        let text = "# railsup mirror\n4.0.1\nv3.4.7\n\n";
+        assert_eq!(parse_release_index(text).unwrap(), vec!["4.0.1", "3.4.7"]);
+
+        assert!(parse_release_index("<html>not found</html>").is_err());
+    }
+
+    #[test]
+    fn file_urls_are_read_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("ruby.tar.gz");
+        fs::write(&src, b"tarball bytes").unwrap();
+        let url = format!("file://{}", src.display());
+
+        let dest = dir.path().join("copy.part");
+        download_with_progress(&url, &dest, &RetryPolicy::default()).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"tarball bytes");
+
+        assert_eq!(fetch_text(&url).unwrap(), "tarball bytes");
+        assert!(fetch_text("file:///nonexistent/index.json").is_err());
+    }
+
+    // ==================== cache / offline tests ====================
+
+    #[test]
+    fn cached_tarball_checked_against_stored_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let tarball = dir.path().join("ruby-4.0.1-linux-x86_64.tar.gz");
+        fs::write(&tarball, b"tarball bytes").unwrap();
+
+        // No stored checksum: must be checked against the published one
+        assert_eq!(
+            check_cached_tarball(&tarball).unwrap(),
+            CachedTarball::Unverified
+        );
+
+        let digest = file_sha256(&tarball).unwrap();
+        let checksum_path = stored_checksum_path(&tarball);
+        assert!(checksum_path.ends_with("ruby-4.0.1-linux-x86_64.tar.gz.sha256"));
+        store_checksum(&tarball, &digest, "ruby-4.0.1.tar.gz").unwrap();
+        assert_eq!(
+            check_cached_tarball(&tarball).unwrap(),
+            CachedTarball::Valid
+        );
+
+        fs::write(&tarball, b"truncated").unwrap();
+        assert_eq!(
+            check_cached_tarball(&tarball).unwrap(),
+            CachedTarball::Corrupt
+        );
+    }
+
+    // ==================== retry / resume tests ====================
+
+    use std::io::{BufRead, BufReader as StdBufReader};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// Serve canned responses in order, one per connection, recording each
+    /// request's headers. `responses` receives the server's base URL.
+    /// Returns a file URL on the server and the recorded requests.
+    fn serve(responses: impl FnOnce(&str) -> Vec<Vec<u8>>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let url = format!("{}/ruby.tar.gz", base);
+        let responses = responses(&base);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = match listener.accept() {
+                    Ok(conn) => conn,
+                    Err(_) => return,
+                };
+                let mut reader = StdBufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                recorded.lock().unwrap().push(head.to_lowercase());
+                let _ = stream.write_all(&response);
+                // Dropping the stream closes the connection, possibly mid-body
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    fn body() -> Vec<u8> {
+        (0..1000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn http(status: &str, headers: &str, body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {}\r\n{}Connection: close\r\n\r\n",
+            status, headers
+        )
+        .into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+        };
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(2), Duration::from_secs(2));
+        assert_eq!(policy.delay(3), Duration::from_secs(4));
+        assert_eq!(policy.delay(6), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn cache_file_version_inverts_version_cache_files() {
+        for version in ["3.4.1", "3.5.0-preview1"] {
+            for name in version_cache_files(version) {
+                assert_eq!(cache_file_version(&name).as_deref(), Some(version));
+                assert_eq!(
+                    cache_file_version(&format!("{}.part", name)).as_deref(),
+                    Some(version)
+                );
+            }
+        }
+        assert_eq!(cache_file_version(RELEASE_INDEX_FILE), None);
+    }
+
+    #[test]
+    fn part_path_appends_suffix() {
+        let part = part_path(Path::new("/cache/ruby-4.0.1-linux-x86_64.tar.gz"));
+        assert_eq!(
+            part,
+            PathBuf::from("/cache/ruby-4.0.1-linux-x86_64.tar.gz.part")
+        );
+    }
+
+    #[test]
+    fn download_resumes_after_dropped_connection() {
+        let body = body();
+        let (url, requests) = serve(|_| {
+            vec![
+                // Promise 1000 bytes, deliver 400, hang up
+                http("200 OK", "Content-Length: 1000\r\n", &body[..400]),
+                http(
+                    "206 Partial Content",
+                    "Content-Range: bytes 400-999/1000\r\nContent-Length: 600\r\n",
+                    &body[400..],
+                ),
+            ]
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("ruby.tar.gz.part");
+        download_with_progress(&url, &dest, &fast_policy(3)).unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[0].contains("range:"));
+        assert!(requests[1].contains("range: bytes=400-"));
+    }
+
+    #[test]
+    fn download_restarts_when_server_ignores_range() {
+        let body = body();
+        let (url, requests) = serve(|_| vec![http("200 OK", "Content-Length: 1000\r\n", &body)]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("ruby.tar.gz.part");
+        fs::write(&dest, b"stale partial bytes").unwrap();
+        download_with_progress(&url, &dest, &fast_policy(3)).unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        assert!(requests.lock().unwrap()[0].contains("range: bytes=19-"));
+    }
+
+    #[test]
+    fn download_retries_server_errors() {
+        let body = body();
+        let (url, requests) = serve(|_| {
+            vec![
+                http("503 Service Unavailable", "Content-Length: 0\r\n", b""),
+                http("200 OK", "Content-Length: 1000\r\n", &body),
+            ]
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("ruby.tar.gz.part");
+        download_with_progress(&url, &dest, &fast_policy(3)).unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn download_does_not_retry_client_errors() {
+        let not_found = http("404 Not Found", "Content-Length: 0\r\n", b"");
+        let (url, requests) = serve(|_| vec![not_found.clone(), not_found]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("ruby.tar.gz.part");
+        let err = download_with_progress(&url, &dest, &fast_policy(3)).unwrap_err();
+
+        assert!(err.to_string().contains("HTTP 404"));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn download_gives_up_after_max_attempts() {
+        let unavailable = http("503 Service Unavailable", "Content-Length: 0\r\n", b"");
+        let (url, requests) =
+            serve(|_| vec![unavailable.clone(), unavailable.clone(), unavailable]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("ruby.tar.gz.part");
+        let err = download_with_progress(&url, &dest, &fast_policy(2)).unwrap_err();
+
+        assert!(format!("{:#}", err).contains("after 2 attempts"));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    // ==================== staged install tests ====================
+
+    /// Build a gzipped Ruby tarball whose `bin/ruby` is a shell script
+    #[cfg(unix)]
+    fn fake_ruby_tarball(dir: &Path, name: &str, ruby_script: &str) -> PathBuf {
+        let tarball = dir.join(format!("{}.tar.gz", name));
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(&tarball).unwrap(),
+            flate2::Compression::fast(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+
+        let mut add = |path: &str, content: &str| {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder
+                .append_data(
+                    &mut header,
+                    format!("{}/{}", name, path),
+                    content.as_bytes(),
+                )
+                .unwrap();
+        };
+        add("bin/ruby", ruby_script);
+        add("bin/gem", "#!/opt/ruby/bin/ruby\nputs 'gem'\n");
+
+        builder.into_inner().unwrap().finish().unwrap();
+        tarball
+    }
+
+    #[cfg(unix)]
+    fn leftover_dot_dirs(root: &Path) -> Vec<String> {
+        fs::read_dir(root)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| n.starts_with('.'))
+            .collect()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn install_tarball_moves_validated_tree_into_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("ruby");
+        let dest = root.join("ruby-4.0.1");
+        let tarball = fake_ruby_tarball(dir.path(), "ruby-4.0.1", "#!/bin/sh\necho ruby 4.0.1\n");
+
+        install_tarball(&tarball, &dest).unwrap();
+
+        assert!(dest.join("bin/ruby").is_file());
+        let gem = fs::read_to_string(dest.join("bin/gem")).unwrap();
+        assert!(gem.starts_with(&format!("#!{}\n", dest.join("bin/ruby").display())));
+        assert!(leftover_dot_dirs(&root).is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn install_tarball_rejects_ruby_that_does_not_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("ruby");
+        let dest = root.join("ruby-4.0.1");
+        let tarball = fake_ruby_tarball(dir.path(), "ruby-4.0.1", "#!/bin/sh\nexit 1\n");
+
+        let err = install_tarball(&tarball, &dest).unwrap_err();
+
+        assert!(err.to_string().contains("failed to run"));
+        assert!(!dest.exists());
+        assert!(leftover_dot_dirs(&root).is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failed_reinstall_keeps_previous_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("ruby");
+        let dest = root.join("ruby-4.0.1");
+        fs::create_dir_all(dest.join("bin")).unwrap();
+        fs::write(dest.join("bin/ruby"), "previous").unwrap();
+        let tarball = fake_ruby_tarball(dir.path(), "ruby-4.0.1", "#!/bin/sh\nexit 1\n");
+
+        assert!(install_tarball(&tarball, &dest).is_err());
+
+        assert_eq!(
+            fs::read_to_string(dest.join("bin/ruby")).unwrap(),
+            "previous"
+        );
+        assert!(leftover_dot_dirs(&root).is_empty());
+    }
+
+    #[test]
+    fn cleanup_stale_installs_restores_and_removes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("ruby");
+        let locks = dir.path().join("locks");
+        let lock_file = |version: &str| locks.join(format!("{}.lock", version));
+        // Pids mean nothing here: the install lock decides
+        let pid = std::process::id();
+
+        // Interrupted mid-swap: backup exists, install is gone
+        let backup = root.join(format!(".backup-ruby-3.4.1-{}", pid));
+        fs::create_dir_all(backup.join("bin")).unwrap();
+        // Abandoned staging directory
+        let staging = root.join(format!(".staging-ruby-4.0.1-{}", pid));
+        fs::create_dir_all(&staging).unwrap();
+        // Staging directory of an install still in progress
+        let live = root.join(format!(".staging-ruby-4.0.0-{}", pid));
+        fs::create_dir_all(&live).unwrap();
+        let _installing = FileLock::try_acquire_at(&lock_file("4.0.0"))
+            .unwrap()
+            .unwrap();
+
+        cleanup_stale_installs_at(&root, lock_file).unwrap();
+
+        assert!(root.join("ruby-3.4.1/bin").is_dir());
+        assert!(!backup.exists());
+        assert!(!staging.exists());
+        assert!(live.exists());
+    }
+
+    // ==================== release index tests ====================
+
+    #[test]
+    fn parse_next_link_finds_next_page() {
+        let header = r#"<https://api.github.com/repositories/1/releases?per_page=100&page=2>; rel="next", <https://api.github.com/repositories/1/releases?per_page=100&page=3>; rel="last""#;
+        assert_eq!(
+            parse_next_link(header).as_deref(),
+            Some("https://api.github.com/repositories/1/releases?per_page=100&page=2")
+        );
+        assert_eq!(parse_next_link(r#"<https://x/?page=1>; rel="prev""#), None);
+    }
+
+    #[test]
+    fn release_index_follows_pagination_and_records_etag() {
+        let (url, requests) = serve(|base| {
+            vec![
+                http(
+                    "200 OK",
+                    &format!(
+                        "ETag: \"abc\"\r\nLink: <{}/releases?page=2>; rel=\"next\"\r\nContent-Length: 24\r\n",
+                        base
+                    ),
+                    br#"[{"tag_name": "v4.0.1"}]"#,
+                ),
+                http(
+                    "200 OK",
+                    "Content-Length: 24\r\n",
+                    br#"[{"tag_name": "v3.4.7"}]"#,
+                ),
+            ]
+        });
+
+        match fetch_release_index(&url, None, Some("secret")).unwrap() {
+            IndexFetch::Fetched { versions, etag } => {
+                assert_eq!(versions, vec!["4.0.1", "3.4.7"]);
+                assert_eq!(etag.as_deref(), Some("\"abc\""));
+            }
+            IndexFetch::NotModified => panic!("expected a fresh index"),
+        }
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("get /releases?page=2 "));
+        assert!(requests
+            .iter()
+            .all(|r| r.contains("authorization: bearer secret")));
+    }
+
+    #[test]
+    fn release_index_sends_if_none_match() {
+        let (url, requests) =
+            serve(|_| vec![http("304 Not Modified", "Content-Length: 0\r\n", b"")]);
+
+        assert!(matches!(
+            fetch_release_index(&url, Some("\"abc\""), None).unwrap(),
+            IndexFetch::NotModified
+        ));
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains("if-none-match: \"abc\""));
+        assert!(!requests[0].contains("authorization"));
+    }
+
+    // ==================== signature tests ====================
+
+    /// Test key and a signature it made over TEST_CHECKSUM (not the release key)
+    const TEST_PUBLIC_KEY: &str = "RWRwQxxjkoBwgzn0SIz/YkCw17D96OcmPGKDhZEWIH0b2LgvFLYRLtjD";
+    const OTHER_PUBLIC_KEY: &str = "RWTplMhS87ShMBU+nsJWxeLgNa9nilPuY+Ar0iJhdcU+3gUS9Ur/C49C";
+    const TEST_CHECKSUM: &str =
+        "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef  ruby-4.0.1-linux-x86_64.tar.gz\n";
+    const TEST_SIGNATURE: &str = "untrusted comment: signature from railsup release key
+RURwQxxjkoBwg1j3a4kGeyaYM5SwiHYSJLlGzKE/MLqNfpgGV3txO2/4nTKGva83FQu6s21AVUDo0towOPT/8+DGGflfWEx2vAk=
+trusted comment: timestamp:1767225600\tfile:ruby-4.0.1-linux-x86_64.tar.gz.sha256
+lGJ549wLngCgqQWpICJfe4zeXI9fc7jsHqIn3VMZzTyLeEt3NjBhp0VCDKgrdpmzWjcAVLXyXiWQjE8bmzKiBw==
+";
+
+    #[test]
+    fn release_public_key_is_valid() {
+        for key in RELEASE_PUBLIC_KEYS {
+            assert!(minisign_verify::PublicKey::from_base64(key).is_ok());
+        }
+    }
+
+    #[test]
+    fn verify_signature_accepts_signed_checksum() {
+        verify_signature(TEST_CHECKSUM.as_bytes(), TEST_SIGNATURE, TEST_PUBLIC_KEY).unwrap();
+    }
+
+    #[test]
+    fn verify_signature_rejects_tampered_checksum() {
+        let tampered = TEST_CHECKSUM.replacen('0', "f", 1);
+        let err =
+            verify_signature(tampered.as_bytes(), TEST_SIGNATURE, TEST_PUBLIC_KEY).unwrap_err();
+        assert!(err.to_string().contains("Signature verification failed"));
+    }
+
+    #[test]
+    fn verify_signature_rejects_other_key_and_garbage() {
+        assert!(
+            verify_signature(TEST_CHECKSUM.as_bytes(), TEST_SIGNATURE, OTHER_PUBLIC_KEY).is_err()
+        );
+        let err = verify_signature(TEST_CHECKSUM.as_bytes(), "not a signature", TEST_PUBLIC_KEY)
+            .unwrap_err();
+        assert!(err.to_string().contains("Malformed signature"));
+    }
+
+    #[test]
+    fn parse_checksum_for_rejects_other_files() {
+        let filename = "ruby-4.0.1-linux-x86_64.tar.gz";
+        assert_eq!(
+            parse_checksum_for(TEST_CHECKSUM, filename).unwrap(),
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+        );
+        assert!(parse_checksum_for("abc  *ruby-4.0.1-linux-x86_64.tar.gz", filename).is_ok());
+        assert!(parse_checksum_for("abc", filename).is_ok());
+        assert!(parse_checksum_for("abc  ruby-3.3.0-linux-x86_64.tar.gz", filename).is_err());
+    }
+
+    // ==================== tarball validation tests ====================
+
+    /// Build a tarball from raw entries, bypassing tar::Builder's own path
+    /// checks so hostile names can be written
+    fn raw_tarball(dir: &Path, entries: &[(&str, EntryType, &str)]) -> PathBuf {
+        let tarball = dir.join("hostile.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(&tarball).unwrap(),
+            flate2::Compression::fast(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+
+        for (path, kind, link) in entries {
+            let mut header = tar::Header::new_old();
+            let name = &mut header.as_old_mut().name;
+            name[..path.len()].copy_from_slice(path.as_bytes());
+            let linkname = &mut header.as_old_mut().linkname;
+            linkname[..link.len()].copy_from_slice(link.as_bytes());
+            header.set_entry_type(*kind);
+            header.set_mode(0o644);
+            let data: &[u8] = if *kind == EntryType::Regular {
+                b"data"
+            } else {
+                b""
+            };
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+
+        builder.into_inner().unwrap().finish().unwrap();
+        tarball
+    }
+
+    fn extract_error(entries: &[(&str, EntryType, &str)]) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let tarball = raw_tarball(dir.path(), entries);
+        let dest = dir.path().join("out");
+        let err = extract_tarball(&tarball, &dest, "ruby-4.0.1").unwrap_err();
+        format!("{:#}", err)
+    }
+
+    #[test]
+    fn extract_accepts_well_formed_tarball() {
+        let dir = tempfile::tempdir().unwrap();
+        let tarball = raw_tarball(
+            dir.path(),
+            &[
+                ("ruby-4.0.1/", EntryType::Directory, ""),
+                ("./ruby-4.0.1/bin/", EntryType::Directory, ""),
+                ("ruby-4.0.1/bin/ruby", EntryType::Regular, ""),
+                ("ruby-4.0.1/bin/ruby4", EntryType::Symlink, "ruby"),
+                ("ruby-4.0.1/lib/", EntryType::Directory, ""),
+                (
+                    "ruby-4.0.1/lib/libruby.so",
+                    EntryType::Symlink,
+                    "../bin/ruby",
+                ),
+            ],
+        );
+        let dest = dir.path().join("out");
+        extract_tarball(&tarball, &dest, "ruby-4.0.1").unwrap();
+        assert!(dest.join("ruby-4.0.1/bin/ruby").is_file());
+    }
+
+    #[test]
+    fn extract_rejects_parent_dir_traversal() {
+        let err = extract_error(&[("ruby-4.0.1/../../evil", EntryType::Regular, "")]);
+        assert!(err.contains("ruby-4.0.1/../../evil"), "{}", err);
+        assert!(err.contains("'..'"), "{}", err);
+    }
+
+    #[test]
+    fn extract_rejects_absolute_paths() {
+        let err = extract_error(&[("/tmp/evil", EntryType::Regular, "")]);
+        assert!(err.contains("/tmp/evil: absolute path"), "{}", err);
+    }
+
+    #[test]
+    fn extract_rejects_entries_outside_expected_root() {
+        let err = extract_error(&[
+            ("ruby-4.0.1/bin/ruby", EntryType::Regular, ""),
+            ("ruby-3.3.0/bin/ruby", EntryType::Regular, ""),
+        ]);
+        assert!(err.contains("ruby-3.3.0/bin/ruby"), "{}", err);
+        assert!(
+            err.contains("expected everything under ruby-4.0.1/"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn extract_rejects_symlinks_escaping_root() {
+        let err = extract_error(&[(
+            "ruby-4.0.1/lib/evil",
+            EntryType::Symlink,
+            "../../../etc/passwd",
+        )]);
+        assert!(err.contains("ruby-4.0.1/lib/evil"), "{}", err);
+        assert!(err.contains("points outside"), "{}", err);
+
+        let err = extract_error(&[("ruby-4.0.1/evil", EntryType::Symlink, "/etc/passwd")]);
+        assert!(
+            err.contains("symlink to absolute path /etc/passwd"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn extract_rejects_chained_symlinks() {
+        // Lexically `dir/evil -> ../..` stays in root, but `dir` is itself a
+        // link to `..`, so it really points above root
+        let err = extract_error(&[
+            ("ruby-4.0.1/x/dir", EntryType::Symlink, ".."),
+            ("ruby-4.0.1/x/dir/evil", EntryType::Symlink, "../.."),
+        ]);
+        assert!(
+            err.contains("goes through symlink ruby-4.0.1/x/dir"),
+            "{}",
+            err
+        );
+
+        let err = extract_error(&[
+            ("ruby-4.0.1/x/", EntryType::Directory, ""),
+            ("ruby-4.0.1/x/a", EntryType::Symlink, "."),
+            ("ruby-4.0.1/evil", EntryType::Symlink, "x/a/../.."),
+        ]);
+        assert!(
+            err.contains("goes through symlink ruby-4.0.1/x/a"),
+            "{}",
+            err
+        );
+
+        // The same link is caught when `x/a` only becomes a link afterwards
+        let err = extract_error(&[
+            ("ruby-4.0.1/x/", EntryType::Directory, ""),
+            ("ruby-4.0.1/evil", EntryType::Symlink, "x/a/../.."),
+            ("ruby-4.0.1/x/a", EntryType::Symlink, "."),
+        ]);
+        assert!(
+            err.contains("an earlier symlink resolves through it"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn extract_rejects_hard_links_outside_root() {
+        let err = extract_error(&[("ruby-4.0.1/evil", EntryType::Link, "etc/passwd")]);
+        assert!(err.contains("Hard link ruby-4.0.1/evil"), "{}", err);
+    }
+
+    #[test]
+    fn extract_rejects_device_files() {
+        let err = extract_error(&[("ruby-4.0.1/dev", EntryType::Char, "")]);
+        assert!(err.contains("device and FIFO"), "{}", err);
+    }
+
+    #[test]
+    fn extract_rejects_empty_archive() {
+        let err = extract_error(&[]);
+        assert!(err.contains("expected ruby-4.0.1/ directory"), "{}", err);
+    }
 }
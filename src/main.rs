@@ -1,9 +1,12 @@
+mod build;
 mod cli;
 mod config;
+mod dotenv;
 mod download;
 mod paths;
 mod platform;
 mod ruby;
+mod usage;
 mod util;
 
 use anyhow::Result;
@@ -20,6 +23,13 @@ fn main() {
 fn run() -> Result<()> {
     let cli = Cli::parse();
 
+    if cli.offline {
+        download::set_offline(true);
+    }
+
+    // Fail with a clear message (not a panic) when there's nowhere to live
+    paths::validate_home()?;
+
     // Handle --agent flag
     if cli.agent {
         cli::agent::run();
@@ -33,11 +43,22 @@ fn run() -> Result<()> {
             force,
             rails_args,
         }) => cli::new::run(&name, force, &rails_args),
-        Some(Commands::Dev { port }) => cli::dev::run(port),
+        Some(Commands::Dev {
+            port,
+            keep_alive,
+            no_dotenv,
+        }) => cli::dev::run(port, keep_alive, no_dotenv),
         Some(Commands::Ruby(cmd)) => cli::ruby::run(cmd),
         Some(Commands::Which { command }) => cli::which::run(&command),
-        Some(Commands::Exec { ruby, command }) => cli::exec::run(ruby, command),
+        Some(Commands::Exec {
+            ruby,
+            no_dotenv,
+            command,
+        }) => cli::exec::run(ruby, no_dotenv, command),
+        Some(Commands::Env { show_values }) => cli::env::run(show_values),
         Some(Commands::ShellInit { shell }) => cli::shell_init::run(shell),
+        Some(Commands::Gc { dry_run, keep }) => cli::gc::run(dry_run, keep),
+        Some(Commands::Du { json, top }) => cli::du::run(json, top),
         Some(Commands::Doctor { json, fix, verbose }) => cli::doctor::run(json, fix, verbose),
         None => {
             // No command provided, show help
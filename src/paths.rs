@@ -1,20 +1,92 @@
 //! Directory structure helpers for railsup
 //!
-//! ~/.railsup/
+//! ~/.railsup/          # or $RAILSUP_HOME
 //! ├── ruby/           # Ruby installations
 //! │   └── 4.0.1/
 //! ├── gems/           # Per-version gems
 //! │   └── 4.0.1/
 //! ├── cache/          # Downloaded tarballs
-//! └── config.toml     # Global config
+//! ├── locks/          # Advisory locks between railsup processes
+//! ├── config.toml     # Global config
+//! └── usage.json      # Projects and the Ruby they last used
+//!
+//! With RAILSUP_XDG=1 (and no RAILSUP_HOME) the tree is split the XDG way:
+//! config in $XDG_CONFIG_HOME/railsup, the cache in $XDG_CACHE_HOME/railsup
+//! and everything else in $XDG_DATA_HOME/railsup.
+
+use anyhow::{bail, Result};
+use std::env;
+use std::path::{Path, PathBuf};
 
-use std::path::PathBuf;
+/// Environment variable that relocates the whole railsup home
+pub const HOME_ENV: &str = "RAILSUP_HOME";
 
-/// Get the railsup home directory (~/.railsup)
+/// Environment variable that opts into the XDG base directory layout
+pub const XDG_ENV: &str = "RAILSUP_XDG";
+
+/// Get the railsup home directory (~/.railsup, $RAILSUP_HOME or the XDG
+/// data directory)
+///
+/// Panics without a home directory; `validate_home` reports that properly
+/// at startup.
 pub fn railsup_dir() -> PathBuf {
-    dirs::home_dir()
-        .expect("Could not determine home directory")
-        .join(".railsup")
+    try_railsup_dir().expect("Could not determine home directory (set RAILSUP_HOME to choose one)")
+}
+
+fn try_railsup_dir() -> Option<PathBuf> {
+    if let Some(home) = env_path(HOME_ENV) {
+        return Some(home);
+    }
+    if xdg_layout() {
+        return xdg_dir("XDG_DATA_HOME", ".local/share");
+    }
+    Some(dirs::home_dir()?.join(".railsup"))
+}
+
+/// Check that a railsup home can be determined and is usable
+pub fn validate_home() -> Result<()> {
+    let Some(home) = try_railsup_dir() else {
+        bail!(
+            "Could not determine a home directory for railsup.\nSet {} to the directory railsup should use.",
+            HOME_ENV
+        );
+    };
+    if home.exists() && !home.is_dir() {
+        bail!(
+            "railsup home {} exists but is not a directory",
+            home.display()
+        );
+    }
+    Ok(())
+}
+
+/// Whether the XDG layout is in effect
+fn xdg_layout() -> bool {
+    env_path(HOME_ENV).is_none()
+        && env::var(XDG_ENV).is_ok_and(|v| !v.is_empty() && v != "0" && v != "false")
+}
+
+/// `$<var>/railsup`, or `~/<fallback>/railsup` when the variable is unset
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    let base = match env_path(var) {
+        Some(base) => base,
+        None => dirs::home_dir()?.join(fallback),
+    };
+    Some(base.join("railsup"))
+}
+
+/// A non-empty path from the environment, made absolute
+///
+/// Installed Rubies get absolute shebangs, so a relative home would break
+/// as soon as railsup ran from another directory.
+fn env_path(var: &str) -> Option<PathBuf> {
+    let value = env::var_os(var).filter(|v| !v.is_empty())?;
+    let path = Path::new(&value);
+    if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else {
+        env::current_dir().ok().map(|cwd| cwd.join(path))
+    }
 }
 
 /// Get the Ruby installations directory (~/.railsup/ruby)
@@ -29,14 +101,34 @@ pub fn gems_dir() -> PathBuf {
 
 /// Get the cache directory (~/.railsup/cache)
 pub fn cache_dir() -> PathBuf {
+    if xdg_layout() {
+        if let Some(dir) = xdg_dir("XDG_CACHE_HOME", ".cache") {
+            return dir;
+        }
+    }
     railsup_dir().join("cache")
 }
 
 /// Get the config file path (~/.railsup/config.toml)
 pub fn config_file() -> PathBuf {
+    if xdg_layout() {
+        if let Some(dir) = xdg_dir("XDG_CONFIG_HOME", ".config") {
+            return dir.join("config.toml");
+        }
+    }
     railsup_dir().join("config.toml")
 }
 
+/// Get the project usage registry path (~/.railsup/usage.json)
+pub fn usage_file() -> PathBuf {
+    railsup_dir().join("usage.json")
+}
+
+/// Get the path of a named lock file (~/.railsup/locks/<name>.lock)
+pub fn lock_file(name: &str) -> PathBuf {
+    railsup_dir().join("locks").join(format!("{}.lock", name))
+}
+
 /// Get the directory for a specific Ruby version
 /// The directory is named `ruby-{version}` (e.g., ruby-4.0.1)
 pub fn ruby_version_dir(version: &str) -> PathBuf {
@@ -76,6 +168,24 @@ pub fn ensure_dirs() -> std::io::Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn env_path_ignores_empty_and_absolutizes_relative() {
+        // A variable no other test touches, so no env mutex is needed
+        let var = "RAILSUP_TEST_PATHS_ENV_PATH";
+        env::set_var(var, "");
+        assert_eq!(env_path(var), None);
+
+        env::set_var(var, "/opt/railsup");
+        assert_eq!(env_path(var), Some(PathBuf::from("/opt/railsup")));
+
+        env::set_var(var, "ci/railsup");
+        assert_eq!(
+            env_path(var),
+            Some(env::current_dir().unwrap().join("ci/railsup"))
+        );
+        env::remove_var(var);
+    }
+
     #[test]
     fn railsup_dir_ends_with_railsup() {
         let path = railsup_dir();
@@ -1,5 +1,12 @@
 //! Platform detection for Ruby binary downloads
 
+use anyhow::{bail, Result};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Read;
+use std::process::Command;
+use std::sync::OnceLock;
+
 /// Detect the operating system for download URL construction
 pub fn detect_os() -> &'static str {
     #[cfg(target_os = "macos")]
@@ -32,6 +39,215 @@ pub fn detect_arch() -> &'static str {
     }
 }
 
+/// Oldest glibc the prebuilt Linux Ruby binaries run on
+pub const MIN_GLIBC: (u32, u32) = (2, 31);
+
+/// C library flavor on Linux
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Libc {
+    /// GNU libc, with its version when it could be determined
+    Glibc(Option<(u32, u32)>),
+    /// musl (Alpine and friends)
+    Musl,
+}
+
+impl fmt::Display for Libc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Libc::Glibc(Some((major, minor))) => write!(f, "glibc {}.{}", major, minor),
+            Libc::Glibc(None) => write!(f, "glibc (unknown version)"),
+            Libc::Musl => write!(f, "musl"),
+        }
+    }
+}
+
+/// Detect the C library (None on macOS)
+///
+/// glibc is probed first: Debian and Ubuntu's `musl` package installs
+/// /lib/ld-musl-*.so.1 on glibc systems, so the loader alone proves nothing.
+/// Next comes the dynamic loader /bin/sh was linked against, and only then
+/// the loader scan.
+pub fn detect_libc() -> Option<Libc> {
+    static LIBC: OnceLock<Option<Libc>> = OnceLock::new();
+    *LIBC.get_or_init(|| {
+        if detect_os() != "linux" {
+            return None;
+        }
+        if let Some(version) = glibc_version() {
+            return Some(Libc::Glibc(Some(version)));
+        }
+        if let Some(libc) = system_interpreter().and_then(|i| libc_for_interpreter(&i)) {
+            return Some(libc);
+        }
+        if has_musl_loader() {
+            return Some(Libc::Musl);
+        }
+        Some(Libc::Glibc(None))
+    })
+}
+
+/// The dynamic loader (PT_INTERP) of /bin/sh
+fn system_interpreter() -> Option<String> {
+    let mut header = vec![];
+    // The program headers and interpreter path sit at the start of the file
+    File::open("/bin/sh")
+        .ok()?
+        .take(64 * 1024)
+        .read_to_end(&mut header)
+        .ok()?;
+    elf_interpreter(&header)
+}
+
+/// Read the PT_INTERP path from the start of an ELF file
+fn elf_interpreter(elf: &[u8]) -> Option<String> {
+    const PT_INTERP: u64 = 3;
+
+    if elf.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    let is_64 = match elf.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let little_endian = *elf.get(5)? == 1;
+    let read = |offset: usize, size: usize| -> Option<u64> {
+        let bytes = elf.get(offset..offset + size)?;
+        let mut value = 0u64;
+        for i in 0..size {
+            let byte = if little_endian {
+                bytes[size - 1 - i]
+            } else {
+                bytes[i]
+            };
+            value = (value << 8) | u64::from(byte);
+        }
+        Some(value)
+    };
+
+    let word = if is_64 { 8 } else { 4 };
+    let (phoff, phentsize, phnum) = if is_64 {
+        (read(0x20, 8)?, read(0x36, 2)?, read(0x38, 2)?)
+    } else {
+        (read(0x1c, 4)?, read(0x2a, 2)?, read(0x2c, 2)?)
+    };
+    for i in 0..phnum {
+        let header = usize::try_from(phoff + i * phentsize).ok()?;
+        if read(header, 4)? != PT_INTERP {
+            continue;
+        }
+        let (offset, size) = if is_64 {
+            (read(header + 8, word)?, read(header + 0x20, word)?)
+        } else {
+            (read(header + 4, word)?, read(header + 0x10, word)?)
+        };
+        let offset = usize::try_from(offset).ok()?;
+        let path = elf.get(offset..offset + usize::try_from(size).ok()?)?;
+        return Some(
+            String::from_utf8_lossy(path)
+                .trim_end_matches('\0')
+                .to_string(),
+        );
+    }
+    None
+}
+
+/// Libc implied by a dynamic loader path
+fn libc_for_interpreter(interpreter: &str) -> Option<Libc> {
+    let name = interpreter.rsplit('/').next()?;
+    if name.starts_with("ld-musl-") {
+        Some(Libc::Musl)
+    } else if name.starts_with("ld-linux") {
+        Some(Libc::Glibc(None))
+    } else {
+        None
+    }
+}
+
+/// musl installs its dynamic loader as /lib/ld-musl-<arch>.so.1
+fn has_musl_loader() -> bool {
+    fs::read_dir("/lib")
+        .map(|entries| {
+            entries.filter_map(|e| e.ok()).any(|e| {
+                e.file_name()
+                    .to_str()
+                    .is_some_and(|n| n.starts_with("ld-musl-"))
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Ask getconf (or ldd as a fallback) for the glibc version
+fn glibc_version() -> Option<(u32, u32)> {
+    let getconf = Command::new("getconf").arg("GNU_LIBC_VERSION").output();
+    if let Ok(output) = getconf {
+        if output.status.success() {
+            if let Some(version) = parse_glibc_version(&String::from_utf8_lossy(&output.stdout)) {
+                return Some(version);
+            }
+        }
+    }
+
+    let output = Command::new("ldd").arg("--version").output().ok()?;
+    // glibc's ldd prints the version on the first line (sometimes to stderr)
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    parse_glibc_version(String::from_utf8_lossy(&text).lines().next()?)
+}
+
+/// Parse "glibc 2.35" or "ldd (Ubuntu GLIBC 2.35-0ubuntu3) 2.35" into (2, 35)
+fn parse_glibc_version(text: &str) -> Option<(u32, u32)> {
+    let last = text.split_whitespace().last()?;
+    let mut parts = last.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts
+        .next()?
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .ok()?;
+    Some((major, minor))
+}
+
+/// Platform tag used in Ruby artifact names
+/// e.g. "darwin-arm64", "linux-x86_64", "linux-musl-x86_64"
+pub fn artifact_platform() -> String {
+    platform_tag(detect_os(), detect_arch(), detect_libc())
+}
+
+fn platform_tag(os: &str, arch: &str, libc: Option<Libc>) -> String {
+    match libc {
+        Some(Libc::Musl) => format!("{}-musl-{}", os, arch),
+        _ => format!("{}-{}", os, arch),
+    }
+}
+
+/// Fail early if the prebuilt Ruby binaries can't run on this system
+pub fn check_compatible() -> Result<()> {
+    check_libc(detect_libc())
+}
+
+fn check_libc(libc: Option<Libc>) -> Result<()> {
+    if let Some(Libc::Glibc(Some(version))) = libc {
+        if version < MIN_GLIBC {
+            bail!(
+                "Prebuilt Ruby binaries need glibc {}.{} or newer, but this system has glibc {}.{}.\n\
+                 They would fail to start with a dynamic loader error. Use a newer distribution \
+                 (or container base image), or build Ruby from source with `railsup ruby build`.",
+                MIN_GLIBC.0,
+                MIN_GLIBC.1,
+                version.0,
+                version.1
+            );
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,4 +263,76 @@ mod tests {
         let arch = detect_arch();
         assert!(arch == "arm64" || arch == "x86_64");
     }
+
+    #[test]
+    fn parse_glibc_version_formats() {
+        assert_eq!(parse_glibc_version("glibc 2.35\n"), Some((2, 35)));
+        assert_eq!(
+            parse_glibc_version("ldd (Ubuntu GLIBC 2.35-0ubuntu3.8) 2.35"),
+            Some((2, 35))
+        );
+        assert_eq!(parse_glibc_version("ldd (GNU libc) 2.17"), Some((2, 17)));
+        assert_eq!(parse_glibc_version("musl libc (x86_64)"), None);
+    }
+
+    /// Minimal 64-bit little-endian ELF header with one PT_INTERP entry
+    fn elf_with_interpreter(interpreter: &str) -> Vec<u8> {
+        let mut elf = vec![0u8; 64 + 56];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4] = 2;
+        elf[5] = 1;
+        elf[0x20..0x28].copy_from_slice(&64u64.to_le_bytes());
+        elf[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+        elf[0x38..0x3a].copy_from_slice(&1u16.to_le_bytes());
+        let path = format!("{}\0", interpreter);
+        elf[64..68].copy_from_slice(&3u32.to_le_bytes());
+        elf[64 + 8..64 + 16].copy_from_slice(&120u64.to_le_bytes());
+        elf[64 + 0x20..64 + 0x28].copy_from_slice(&(path.len() as u64).to_le_bytes());
+        elf.extend_from_slice(path.as_bytes());
+        elf
+    }
+
+    #[test]
+    fn elf_interpreter_identifies_libc() {
+        let musl = elf_with_interpreter("/lib/ld-musl-x86_64.so.1");
+        assert_eq!(
+            elf_interpreter(&musl).as_deref(),
+            Some("/lib/ld-musl-x86_64.so.1")
+        );
+        assert_eq!(
+            libc_for_interpreter("/lib/ld-musl-x86_64.so.1"),
+            Some(Libc::Musl)
+        );
+
+        let glibc = elf_with_interpreter("/lib64/ld-linux-x86-64.so.2");
+        let interpreter = elf_interpreter(&glibc).unwrap();
+        assert_eq!(libc_for_interpreter(&interpreter), Some(Libc::Glibc(None)));
+
+        assert_eq!(elf_interpreter(b"#!/bin/sh\n"), None);
+        assert_eq!(elf_interpreter(&glibc[..70]), None);
+    }
+
+    #[test]
+    fn platform_tag_marks_musl() {
+        assert_eq!(platform_tag("darwin", "arm64", None), "darwin-arm64");
+        assert_eq!(
+            platform_tag("linux", "x86_64", Some(Libc::Glibc(Some((2, 35))))),
+            "linux-x86_64"
+        );
+        assert_eq!(
+            platform_tag("linux", "x86_64", Some(Libc::Musl)),
+            "linux-musl-x86_64"
+        );
+    }
+
+    #[test]
+    fn check_libc_rejects_old_glibc() {
+        assert!(check_libc(None).is_ok());
+        assert!(check_libc(Some(Libc::Musl)).is_ok());
+        assert!(check_libc(Some(Libc::Glibc(None))).is_ok());
+        assert!(check_libc(Some(Libc::Glibc(Some(MIN_GLIBC)))).is_ok());
+
+        let err = check_libc(Some(Libc::Glibc(Some((2, 17))))).unwrap_err();
+        assert!(err.to_string().contains("this system has glibc 2.17"));
+    }
 }
@@ -0,0 +1,258 @@
+//! Rubies installed by other tools
+//!
+//! `railsup ruby link` registers an existing Ruby prefix (from rbenv, asdf,
+//! mise, rvm, Homebrew or anywhere else) as a symlink under
+//! ~/.railsup/ruby/ruby-<name>. Linked Rubies get their own gems directory
+//! like downloaded ones; railsup never modifies the prefix itself.
+
+use crate::paths;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A Ruby installation found outside railsup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalRuby {
+    /// Tool that installed it (rbenv, asdf, mise, rvm, homebrew)
+    pub manager: &'static str,
+    /// Version name to register it under
+    pub version: String,
+    /// Install prefix (contains bin/ruby)
+    pub prefix: PathBuf,
+}
+
+/// Where Homebrew keeps its Ruby (Apple Silicon, then Intel)
+pub const HOMEBREW_RUBY_PREFIXES: &[&str] = &["/opt/homebrew/opt/ruby", "/usr/local/opt/ruby"];
+
+/// A version manager railsup knows where to look for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionManager {
+    pub name: &'static str,
+    /// Its data directory
+    pub root: PathBuf,
+    /// Directory holding one prefix per installed Ruby
+    pub rubies: PathBuf,
+    /// Part of its PATH entries (shims or bin directories)
+    pub path_marker: &'static str,
+}
+
+/// Version managers and where they keep their Rubies
+///
+/// Shared by `ruby link --auto` and `railsup doctor` so both look in the
+/// same places.
+pub fn version_managers() -> Vec<VersionManager> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let data_dir = |var: &str, default: &str| {
+        std::env::var_os(var)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(default))
+    };
+    let manager = |name, root: PathBuf, rubies: &str, path_marker| VersionManager {
+        name,
+        rubies: root.join(rubies),
+        root,
+        path_marker,
+    };
+
+    vec![
+        manager("rbenv", home.join(".rbenv"), "versions", ".rbenv/shims"),
+        manager(
+            "asdf",
+            data_dir("ASDF_DATA_DIR", ".asdf"),
+            "installs/ruby",
+            ".asdf/shims",
+        ),
+        manager("rvm", home.join(".rvm"), "rubies", ".rvm"),
+        manager(
+            "mise",
+            data_dir("MISE_DATA_DIR", ".local/share/mise"),
+            "installs/ruby",
+            "mise/shims",
+        ),
+    ]
+}
+
+/// Find Rubies installed by other version managers and Homebrew
+pub fn discover() -> Vec<ExternalRuby> {
+    let mut found = vec![];
+    for manager in version_managers() {
+        found.extend(scan_versions_dir(manager.name, &manager.rubies));
+    }
+
+    for prefix in HOMEBREW_RUBY_PREFIXES {
+        let prefix = Path::new(prefix);
+        if let Some(version) = ruby_version_at(prefix) {
+            found.push(ExternalRuby {
+                manager: "homebrew",
+                version,
+                prefix: prefix.to_path_buf(),
+            });
+        }
+    }
+
+    found
+}
+
+/// List `<dir>/<version>` installs that contain bin/ruby
+///
+/// Symlinked entries are skipped: mise and asdf use them for aliases such
+/// as `3.4 -> 3.4.1`, which would otherwise be imported twice.
+fn scan_versions_dir(manager: &'static str, dir: &Path) -> Vec<ExternalRuby> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut found: Vec<ExternalRuby> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter(|e| e.path().join("bin/ruby").is_file())
+        .map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            ExternalRuby {
+                manager,
+                // rvm names MRI installs ruby-X.Y.Z
+                version: name.strip_prefix("ruby-").unwrap_or(&name).to_string(),
+                prefix: e.path(),
+            }
+        })
+        .collect();
+    found.sort_by(|a, b| a.version.cmp(&b.version));
+    found
+}
+
+/// Ask a Ruby prefix for its version (used where the path doesn't say)
+fn ruby_version_at(prefix: &Path) -> Option<String> {
+    let ruby = prefix.join("bin/ruby");
+    if !ruby.is_file() {
+        return None;
+    }
+    let output = Command::new(ruby)
+        .args(["-e", "print RUBY_VERSION"])
+        .output()
+        .ok()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !version.is_empty()).then_some(version)
+}
+
+/// Target of a linked Ruby, or None for downloaded/built installs
+pub fn linked_prefix(version: &str) -> Option<PathBuf> {
+    fs::read_link(paths::ruby_version_dir(version)).ok()
+}
+
+/// Outcome of linking a single Ruby
+#[derive(Debug, PartialEq, Eq)]
+pub enum LinkResult {
+    Linked,
+    AlreadyLinked,
+}
+
+/// Register `prefix` as Ruby `name`
+///
+/// An existing link is only replaced with `force`; a Ruby that railsup
+/// downloaded or built is never replaced.
+pub fn link(name: &str, prefix: &Path, force: bool) -> Result<LinkResult> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        bail!("Invalid Ruby version name: '{}'", name);
+    }
+    let prefix = fs::canonicalize(prefix)
+        .with_context(|| format!("Ruby prefix not found: {}", prefix.display()))?;
+    if !prefix.join("bin/ruby").is_file() {
+        bail!(
+            "{} doesn't look like a Ruby install (no bin/ruby)",
+            prefix.display()
+        );
+    }
+
+    let dest = paths::ruby_version_dir(name);
+    if let Some(existing) = linked_prefix(name) {
+        if existing == prefix {
+            return Ok(LinkResult::AlreadyLinked);
+        }
+        if !force {
+            bail!(
+                "Ruby {} is already linked to {}\nUse --force to replace the link",
+                name,
+                existing.display()
+            );
+        }
+        fs::remove_file(&dest)?;
+    } else if dest.exists() {
+        bail!(
+            "Ruby {} is already installed by railsup at {}",
+            name,
+            dest.display()
+        );
+    }
+
+    fs::create_dir_all(paths::ruby_dir())?;
+    symlink_dir(&prefix, &dest)?;
+
+    // Gems stay isolated per version, exactly like downloaded Rubies
+    fs::create_dir_all(paths::gems_version_dir(name))?;
+    Ok(LinkResult::Linked)
+}
+
+#[cfg(unix)]
+fn symlink_dir(target: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link)
+        .with_context(|| format!("Failed to link {} -> {}", link.display(), target.display()))
+}
+
+#[cfg(not(unix))]
+fn symlink_dir(_target: &Path, _link: &Path) -> Result<()> {
+    bail!("Linking external Rubies is only supported on macOS and Linux")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fake_install(dir: &Path) {
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        fs::write(dir.join("bin/ruby"), "").unwrap();
+    }
+
+    #[test]
+    fn scan_finds_installs_with_ruby_binary() {
+        let temp = TempDir::new().unwrap();
+        fake_install(&temp.path().join("3.4.1"));
+        fake_install(&temp.path().join("3.3.6"));
+        fs::create_dir_all(temp.path().join("broken")).unwrap();
+
+        let found = scan_versions_dir("rbenv", temp.path());
+        let versions: Vec<_> = found.iter().map(|r| r.version.as_str()).collect();
+        assert_eq!(versions, vec!["3.3.6", "3.4.1"]);
+        assert_eq!(found[0].manager, "rbenv");
+        assert_eq!(found[0].prefix, temp.path().join("3.3.6"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn scan_skips_alias_symlinks_and_strips_rvm_prefix() {
+        let temp = TempDir::new().unwrap();
+        fake_install(&temp.path().join("ruby-3.4.1"));
+        std::os::unix::fs::symlink(temp.path().join("ruby-3.4.1"), temp.path().join("3.4"))
+            .unwrap();
+
+        let found = scan_versions_dir("rvm", temp.path());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].version, "3.4.1");
+    }
+
+    #[test]
+    fn scan_missing_dir_is_empty() {
+        assert!(scan_versions_dir("asdf", Path::new("/nonexistent/railsup")).is_empty());
+    }
+
+    #[test]
+    fn version_managers_keep_rubies_under_their_root() {
+        let managers = version_managers();
+        let names: Vec<_> = managers.iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["rbenv", "asdf", "rvm", "mise"]);
+        for manager in &managers {
+            assert!(manager.rubies.starts_with(&manager.root));
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Moving gems between per-version gem directories
+//!
+//! Used by `ruby upgrade` when a project moves to a new patch release.
+//! Patch releases of a series share an ABI, so copying (including compiled
+//! extensions) is safe there; `reinstall` rebuilds each gem instead.
+
+use crate::paths;
+use crate::util::process;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// List `(name, version)` for every gem installed in a gem home
+pub fn installed_gems(gem_home: &Path) -> Result<Vec<(String, String)>> {
+    let specs = gem_home.join("specifications");
+    if !specs.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut gems = vec![];
+    for entry in fs::read_dir(&specs)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if let Some(gem) = name.strip_suffix(".gemspec").and_then(parse_spec_name) {
+            gems.push(gem);
+        }
+    }
+    gems.sort();
+    Ok(gems)
+}
+
+/// Split a spec name like `nokogiri-1.16.0-x86_64-linux` into name and
+/// version (the platform suffix is dropped; `gem install` picks it again)
+fn parse_spec_name(stem: &str) -> Option<(String, String)> {
+    let split = stem
+        .match_indices('-')
+        .map(|(i, _)| i)
+        .find(|&i| stem[i + 1..].starts_with(|c: char| c.is_ascii_digit()))?;
+    let name = &stem[..split];
+    let version = stem[split + 1..].split('-').next()?;
+    Some((name.to_string(), version.to_string()))
+}
+
+/// Copy gems from `from`'s gem directory into `to`'s, returning how many
+///
+/// Files already present in the target are kept. Gem executables are
+/// repointed from the old Ruby to the new one.
+pub fn copy_gems(from: &str, to: &str) -> Result<usize> {
+    let source = paths::gems_version_dir(from);
+    let target = paths::gems_version_dir(to);
+    let count = installed_gems(&source)?.len();
+    if count == 0 {
+        return Ok(0);
+    }
+
+    copy_dir(&source, &target)
+        .with_context(|| format!("Failed to copy gems from {}", source.display()))?;
+
+    let old_ruby = paths::ruby_bin_dir(from).join("ruby");
+    let new_ruby = paths::ruby_bin_dir(to).join("ruby");
+    repoint_shebangs(
+        &target.join("bin"),
+        &old_ruby.display().to_string(),
+        &new_ruby.display().to_string(),
+    )?;
+
+    Ok(count)
+}
+
+/// Reinstall `from`'s gems into `to` with `to`'s `gem`; returns gems that
+/// failed to install
+pub fn reinstall_gems(from: &str, to: &str) -> Result<Vec<String>> {
+    let gems = installed_gems(&paths::gems_version_dir(from))?;
+    let ruby_bin = paths::ruby_bin_dir(to);
+    let gem_home = paths::gems_version_dir(to);
+    let gem = ruby_bin.join("gem");
+
+    let mut failed = vec![];
+    for (name, version) in gems {
+        let status = process::run_streaming_with_full_env(
+            &gem.display().to_string(),
+            &["install", &name, "-v", &version, "--no-document"],
+            None,
+            Some(&ruby_bin),
+            Some(&gem_home),
+        )?;
+        if !status.success() {
+            failed.push(format!("{} {}", name, version));
+        }
+    }
+    Ok(failed)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let dest = to.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &dest)?;
+        } else if dest.symlink_metadata().is_ok() {
+            continue;
+        } else if file_type.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &dest)?;
+        } else {
+            fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace `old` with `new` in the shebang of scripts in `bin_dir`
+fn repoint_shebangs(bin_dir: &Path, old: &str, new: &str) -> Result<()> {
+    if !bin_dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(bin_dir)? {
+        let path = entry?.path();
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue; // Binary file
+        };
+        let first_line = content.lines().next().unwrap_or("");
+        if first_line.starts_with("#!") && first_line.contains(old) {
+            let updated = content.replacen(old, new, 1);
+            fs::write(&path, updated)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parse_spec_name_handles_dashes_and_platforms() {
+        assert_eq!(
+            parse_spec_name("rails-8.0.1"),
+            Some(("rails".to_string(), "8.0.1".to_string()))
+        );
+        assert_eq!(
+            parse_spec_name("websocket-driver-0.7.6"),
+            Some(("websocket-driver".to_string(), "0.7.6".to_string()))
+        );
+        assert_eq!(
+            parse_spec_name("nokogiri-1.16.0-x86_64-linux"),
+            Some(("nokogiri".to_string(), "1.16.0".to_string()))
+        );
+        assert_eq!(parse_spec_name("broken"), None);
+    }
+
+    #[test]
+    fn copy_dir_keeps_existing_files_and_repoints_bins() {
+        let temp = TempDir::new().unwrap();
+        let from = temp.path().join("from");
+        let to = temp.path().join("to");
+        fs::create_dir_all(from.join("bin")).unwrap();
+        fs::create_dir_all(to.join("bin")).unwrap();
+        fs::write(
+            from.join("bin/rails"),
+            "#!/r/3.4.1/bin/ruby\nload 'rails'\n",
+        )
+        .unwrap();
+        fs::write(from.join("bin/rake"), "#!/r/3.4.1/bin/ruby\nold\n").unwrap();
+        fs::write(to.join("bin/rake"), "#!/r/3.4.2/bin/ruby\nnew\n").unwrap();
+
+        copy_dir(&from, &to).unwrap();
+        repoint_shebangs(&to.join("bin"), "/r/3.4.1/bin/ruby", "/r/3.4.2/bin/ruby").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(to.join("bin/rails")).unwrap(),
+            "#!/r/3.4.2/bin/ruby\nload 'rails'\n"
+        );
+        assert_eq!(
+            fs::read_to_string(to.join("bin/rake")).unwrap(),
+            "#!/r/3.4.2/bin/ruby\nnew\n"
+        );
+    }
+}
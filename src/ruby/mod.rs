@@ -1,6 +1,16 @@
 // System Ruby detection (kept for potential fallback use)
 #[allow(dead_code)]
 mod detect;
+pub mod external;
+pub mod gems;
+mod requirement;
+mod resolve;
 
 #[allow(unused_imports)]
 pub use detect::{detect, RubyError, RubyInfo};
+pub use requirement::VersionRequirement;
+pub use resolve::{
+    find_project_ruby, parse_gemfile_ruby, parse_railsup_toml, parse_ruby_version_file,
+    resolve_global, resolve_installed, resolve_or_install_ruby, resolve_ruby_version,
+    rewrite_project_pin,
+};
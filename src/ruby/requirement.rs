@@ -0,0 +1,346 @@
+//! Ruby version requirements
+//!
+//! Project files may pin an exact version (`3.4.1`) or a requirement:
+//! - `3.4` — any 3.4.x release
+//! - `~> 3.3.2` — pessimistic (>= 3.3.2, < 3.4)
+//! - `>= 3.3, < 4` — comma-separated comparisons
+//! - `latest` — newest release
+//! - `latest-3.3` — newest 3.3.x release (a channel, like `latest`)
+
+use crate::download::{self, compare_versions};
+use anyhow::{bail, Result};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Comparison operator in a requirement clause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Pessimistic,
+    Series,
+}
+
+/// A single clause such as `>= 3.3`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    op: Op,
+    version: Vec<u32>,
+}
+
+/// A parsed Ruby version requirement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionRequirement {
+    /// Newest release available
+    Latest,
+    /// A fully specified version (including prereleases like `3.5.0-preview1`)
+    Exact(String),
+    /// One or more constraints that must all hold
+    Range {
+        raw: String,
+        constraints: Vec<Constraint>,
+    },
+}
+
+impl VersionRequirement {
+    /// Parse a requirement string
+    pub fn parse(input: &str) -> Result<Self> {
+        let raw = input.trim();
+        if raw.is_empty() {
+            bail!("Empty Ruby version requirement");
+        }
+        if raw == "latest" {
+            return Ok(VersionRequirement::Latest);
+        }
+
+        // Channels: `latest-3.3` tracks the newest patch of a series
+        if let Some(series) = raw.strip_prefix("latest-") {
+            return match parse_numeric(series) {
+                Some(version) if version.len() == 2 => Ok(VersionRequirement::Range {
+                    raw: raw.to_string(),
+                    constraints: vec![Constraint {
+                        op: Op::Series,
+                        version,
+                    }],
+                }),
+                _ => bail!(
+                    "Invalid Ruby channel '{}' (expected latest or latest-X.Y)",
+                    raw
+                ),
+            };
+        }
+
+        let raw = raw.trim_start_matches("ruby-");
+
+        // A bare, fully specified version is an exact pin
+        if !raw.contains(',') && !raw.starts_with(['~', '>', '<', '=', '!']) {
+            let numeric = parse_numeric(raw);
+            if numeric.is_none() || numeric.as_ref().is_some_and(|n| n.len() >= 3) {
+                return Ok(VersionRequirement::Exact(raw.to_string()));
+            }
+        }
+
+        let constraints = raw
+            .split(',')
+            .map(|clause| parse_clause(clause.trim()))
+            .collect::<Option<Vec<_>>>();
+
+        match constraints {
+            Some(constraints) if !constraints.is_empty() => Ok(VersionRequirement::Range {
+                raw: raw.to_string(),
+                constraints,
+            }),
+            _ => bail!("Invalid Ruby version requirement: '{}'", input.trim()),
+        }
+    }
+
+    /// Whether this requirement names exactly one version
+    pub fn is_exact(&self) -> bool {
+        matches!(self, VersionRequirement::Exact(_))
+    }
+
+    /// Whether this is a channel (`latest`, `latest-X.Y`) that `ruby upgrade`
+    /// moves forward
+    pub fn is_channel(&self) -> bool {
+        match self {
+            VersionRequirement::Latest => true,
+            VersionRequirement::Range { raw, .. } => raw.starts_with("latest-"),
+            VersionRequirement::Exact(_) => false,
+        }
+    }
+
+    /// Check whether a concrete version satisfies this requirement
+    pub fn matches(&self, version: &str) -> bool {
+        match self {
+            VersionRequirement::Exact(v) => v == version,
+            VersionRequirement::Latest => parse_numeric(version).is_some(),
+            VersionRequirement::Range { constraints, .. } => match parse_numeric(version) {
+                // Prereleases only satisfy exact pins
+                Some(v) => constraints.iter().all(|c| c.matches(&v)),
+                None => false,
+            },
+        }
+    }
+
+    /// Pick the newest version from `versions` that satisfies this requirement
+    pub fn best_match(&self, versions: &[String]) -> Option<String> {
+        versions
+            .iter()
+            .filter(|v| self.matches(v))
+            .max_by(|a, b| compare_versions(a, b))
+            .cloned()
+    }
+
+    /// Find the newest downloadable version that satisfies this requirement
+    pub fn latest_available(&self) -> Result<Option<String>> {
+        let available = download::fetch_available_versions()?;
+
+        if let VersionRequirement::Range { constraints, .. } = self {
+            if let [Constraint {
+                op: Op::Series,
+                version,
+            }] = constraints.as_slice()
+            {
+                if version.len() == 2 {
+                    let series = format!("{}.{}", version[0], version[1]);
+                    return Ok(download::find_latest_in_series(&series, &available));
+                }
+            }
+        }
+
+        Ok(self.best_match(&available))
+    }
+}
+
+impl fmt::Display for VersionRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionRequirement::Latest => write!(f, "latest"),
+            VersionRequirement::Exact(v) => write!(f, "{}", v),
+            VersionRequirement::Range { raw, .. } => write!(f, "{}", raw),
+        }
+    }
+}
+
+impl Constraint {
+    fn matches(&self, version: &[u32]) -> bool {
+        let ord = compare_parts(version, &self.version);
+        match self.op {
+            Op::Eq => ord == Ordering::Equal,
+            Op::Ne => ord != Ordering::Equal,
+            Op::Gt => ord == Ordering::Greater,
+            Op::Ge => ord != Ordering::Less,
+            Op::Lt => ord == Ordering::Less,
+            Op::Le => ord != Ordering::Greater,
+            Op::Series => version.starts_with(&self.version),
+            Op::Pessimistic => {
+                ord != Ordering::Less
+                    && compare_parts(version, &pessimistic_upper_bound(&self.version))
+                        == Ordering::Less
+            }
+        }
+    }
+}
+
+/// Upper bound for `~>`: `~> 3.3.2` → 3.4, `~> 3.3` → 4, `~> 3` → 4
+fn pessimistic_upper_bound(version: &[u32]) -> Vec<u32> {
+    let mut bound = if version.len() > 1 {
+        version[..version.len() - 1].to_vec()
+    } else {
+        version.to_vec()
+    };
+    if let Some(last) = bound.last_mut() {
+        *last += 1;
+    }
+    bound
+}
+
+/// Compare numeric versions, treating missing components as zero
+fn compare_parts(a: &[u32], b: &[u32]) -> Ordering {
+    let len = a.len().max(b.len());
+    for i in 0..len {
+        let av = a.get(i).copied().unwrap_or(0);
+        let bv = b.get(i).copied().unwrap_or(0);
+        match av.cmp(&bv) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    Ordering::Equal
+}
+
+fn parse_clause(clause: &str) -> Option<Constraint> {
+    let (op, rest) = if let Some(rest) = clause.strip_prefix("~>") {
+        (Op::Pessimistic, rest)
+    } else if let Some(rest) = clause.strip_prefix(">=") {
+        (Op::Ge, rest)
+    } else if let Some(rest) = clause.strip_prefix("<=") {
+        (Op::Le, rest)
+    } else if let Some(rest) = clause.strip_prefix("!=") {
+        (Op::Ne, rest)
+    } else if let Some(rest) = clause.strip_prefix('>') {
+        (Op::Gt, rest)
+    } else if let Some(rest) = clause.strip_prefix('<') {
+        (Op::Lt, rest)
+    } else if let Some(rest) = clause.strip_prefix('=') {
+        (Op::Eq, rest)
+    } else {
+        (Op::Series, clause)
+    };
+
+    let version = parse_numeric(rest.trim())?;
+    Some(Constraint { op, version })
+}
+
+/// Parse a purely numeric dotted version ("3.4.1" → [3, 4, 1])
+fn parse_numeric(version: &str) -> Option<Vec<u32>> {
+    if version.is_empty() {
+        return None;
+    }
+    version.split('.').map(|p| p.parse().ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(list: &[&str]) -> Vec<String> {
+        list.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_exact_and_latest() {
+        assert_eq!(
+            VersionRequirement::parse("3.4.1").unwrap(),
+            VersionRequirement::Exact("3.4.1".to_string())
+        );
+        assert_eq!(
+            VersionRequirement::parse("ruby-3.4.1").unwrap(),
+            VersionRequirement::Exact("3.4.1".to_string())
+        );
+        assert!(VersionRequirement::parse("3.5.0-preview1")
+            .unwrap()
+            .is_exact());
+        assert_eq!(
+            VersionRequirement::parse("latest").unwrap(),
+            VersionRequirement::Latest
+        );
+        assert!(!VersionRequirement::parse("3.4").unwrap().is_exact());
+    }
+
+    #[test]
+    fn parse_rejects_garbage() {
+        assert!(VersionRequirement::parse("").is_err());
+        assert!(VersionRequirement::parse(">= abc").is_err());
+        assert!(VersionRequirement::parse(">= 3.3,").is_err());
+    }
+
+    #[test]
+    fn series_matches_patch_releases() {
+        let req = VersionRequirement::parse("3.4").unwrap();
+        assert!(req.matches("3.4.0"));
+        assert!(req.matches("3.4.7"));
+        assert!(!req.matches("3.3.9"));
+        assert!(!req.matches("3.40.0"));
+        assert!(!req.matches("3.4.0-preview1"));
+    }
+
+    #[test]
+    fn pessimistic_operator() {
+        let req = VersionRequirement::parse("~> 3.3.2").unwrap();
+        assert!(req.matches("3.3.2"));
+        assert!(req.matches("3.3.9"));
+        assert!(!req.matches("3.3.1"));
+        assert!(!req.matches("3.4.0"));
+
+        let req = VersionRequirement::parse("~> 3.3").unwrap();
+        assert!(req.matches("3.9.0"));
+        assert!(!req.matches("4.0.0"));
+    }
+
+    #[test]
+    fn comparison_list() {
+        let req = VersionRequirement::parse(">= 3.3, < 4").unwrap();
+        assert!(req.matches("3.3.0"));
+        assert!(req.matches("3.4.7"));
+        assert!(!req.matches("3.2.9"));
+        assert!(!req.matches("4.0.0"));
+    }
+
+    #[test]
+    fn best_match_picks_newest() {
+        let installed = versions(&["3.3.5", "3.4.1", "3.4.2", "4.0.1"]);
+        let req = VersionRequirement::parse("3.4").unwrap();
+        assert_eq!(req.best_match(&installed), Some("3.4.2".to_string()));
+
+        let req = VersionRequirement::parse("latest").unwrap();
+        assert_eq!(req.best_match(&installed), Some("4.0.1".to_string()));
+
+        let req = VersionRequirement::parse("~> 3.2.0").unwrap();
+        assert_eq!(req.best_match(&installed), None);
+    }
+
+    #[test]
+    fn series_channel() {
+        let req = VersionRequirement::parse("latest-3.3").unwrap();
+        assert!(req.is_channel());
+        assert!(req.matches("3.3.7"));
+        assert!(!req.matches("3.4.0"));
+        assert_eq!(req.to_string(), "latest-3.3");
+
+        assert!(VersionRequirement::parse("latest").unwrap().is_channel());
+        assert!(!VersionRequirement::parse("3.3").unwrap().is_channel());
+        assert!(VersionRequirement::parse("latest-3").is_err());
+        assert!(VersionRequirement::parse("latest-abc").is_err());
+    }
+
+    #[test]
+    fn display_keeps_original_text() {
+        let req = VersionRequirement::parse(">= 3.3, < 4").unwrap();
+        assert_eq!(req.to_string(), ">= 3.3, < 4");
+    }
+}
@@ -0,0 +1,689 @@
+//! Ruby version resolution shared by every command
+//!
+//! Precedence (first match wins):
+//! 1. Project files, nearest directory first (walking up from the working
+//!    directory). Within one directory the order is:
+//!    `railsup.toml` → `.ruby-version` → `.tool-versions` → Gemfile `ruby`.
+//!    The pin may be a requirement (see `requirement.rs`); the newest
+//!    installed match wins.
+//! 2. Global default from ~/.railsup/config.toml
+//! 3. Latest installed version
+//!
+//! Any of these may name an alias from `[ruby.aliases]` in config.toml or a
+//! channel (`latest`, `latest-3.3`); both resolve to the newest installed
+//! match.
+//!
+//! A missing project pin can be installed on first use, controlled by
+//! `RAILSUP_AUTO_INSTALL` or `[ruby] auto_install` (always, prompt, never).
+
+use super::requirement::VersionRequirement;
+use crate::cli::ruby::{confirm, list_installed_versions};
+use crate::config::{AutoInstall, Config};
+use crate::{download, paths, util::ui};
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+/// Where a resolved Ruby version came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionSource {
+    RailsupToml(PathBuf),
+    RubyVersionFile(PathBuf),
+    ToolVersions(PathBuf),
+    Gemfile(PathBuf),
+    GlobalDefault,
+    LatestInstalled,
+}
+
+impl VersionSource {
+    /// Whether the version was pinned by a project file
+    pub fn is_project(&self) -> bool {
+        !matches!(
+            self,
+            VersionSource::GlobalDefault | VersionSource::LatestInstalled
+        )
+    }
+
+    /// Short label for the source (file name or description)
+    pub fn label(&self) -> &'static str {
+        match self {
+            VersionSource::RailsupToml(_) => "railsup.toml",
+            VersionSource::RubyVersionFile(_) => ".ruby-version",
+            VersionSource::ToolVersions(_) => ".tool-versions",
+            VersionSource::Gemfile(_) => "Gemfile",
+            VersionSource::GlobalDefault => "global default",
+            VersionSource::LatestInstalled => "latest installed",
+        }
+    }
+
+    /// Path of the project file, if the version came from one
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            VersionSource::RailsupToml(p)
+            | VersionSource::RubyVersionFile(p)
+            | VersionSource::ToolVersions(p)
+            | VersionSource::Gemfile(p) => Some(p),
+            VersionSource::GlobalDefault | VersionSource::LatestInstalled => None,
+        }
+    }
+}
+
+impl fmt::Display for VersionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path() {
+            Some(path) => write!(f, "{}", path.display()),
+            None => write!(f, "{}", self.label()),
+        }
+    }
+}
+
+/// A Ruby version together with where it was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRuby {
+    pub version: String,
+    pub source: VersionSource,
+}
+
+/// Resolve the Ruby version for the current working directory
+pub fn resolve_ruby_version() -> Result<ResolvedRuby> {
+    let current_dir = std::env::current_dir()?;
+    resolve(&current_dir)
+}
+
+/// Resolve the Ruby version for a directory using the full precedence chain
+///
+/// Fails if a project pins a version that isn't installed.
+pub fn resolve(start: &Path) -> Result<ResolvedRuby> {
+    if let Some(project) = find_project_ruby(start) {
+        return match check_project(project)? {
+            ProjectPin::Installed(resolved) => Ok(resolved),
+            ProjectPin::Missing(missing) => Err(missing.into_error(false)),
+        };
+    }
+
+    resolve_global()
+}
+
+/// Resolve the Ruby version for the current working directory, installing a
+/// missing project pin according to the auto-install policy
+pub fn resolve_or_install_ruby() -> Result<ResolvedRuby> {
+    let current_dir = std::env::current_dir()?;
+    let Some(project) = find_project_ruby(&current_dir) else {
+        return resolve_global();
+    };
+
+    let missing = match check_project(project)? {
+        ProjectPin::Installed(resolved) => return Ok(resolved),
+        ProjectPin::Missing(missing) => missing,
+    };
+
+    let Some(version) = missing.candidate.clone() else {
+        return Err(missing.into_error(false));
+    };
+
+    let install = match Config::load()?.auto_install()? {
+        AutoInstall::Always => true,
+        AutoInstall::Never => false,
+        AutoInstall::Prompt => {
+            if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+                return Err(missing.into_error(true));
+            }
+            confirm(&format!(
+                "Ruby {} is required by {} but not installed. Install it now?",
+                version, missing.source
+            ))?
+        }
+    };
+
+    if !install {
+        return Err(missing.into_error(false));
+    }
+
+    // Progress goes to stderr: `$(railsup which ruby)` captures stdout
+    ui::progress(&format!(
+        "Installing Ruby {} (required by {})...",
+        version,
+        missing.source.label()
+    ));
+    download::download_ruby(&version, false)?;
+    ui::progress_success(&format!("Ruby {} installed", version));
+
+    Ok(ResolvedRuby {
+        version,
+        source: missing.source,
+    })
+}
+
+/// Outcome of checking a project pin against installed versions
+enum ProjectPin {
+    Installed(ResolvedRuby),
+    Missing(MissingRuby),
+}
+
+/// A project pin that no installed Ruby satisfies
+struct MissingRuby {
+    requirement: VersionRequirement,
+    source: VersionSource,
+    /// Version that would satisfy the pin, if one is known
+    candidate: Option<String>,
+    /// The release list was fetched and nothing matched
+    no_match: bool,
+}
+
+impl MissingRuby {
+    fn into_error(self, suggest_auto_install: bool) -> anyhow::Error {
+        let mut message = match (&self.candidate, self.requirement.is_exact()) {
+            (Some(version), true) => format!(
+                "Project requires Ruby {} (from {}) but it's not installed.\nRun: railsup ruby install {}",
+                version, self.source, version
+            ),
+            (Some(version), false) => format!(
+                "Project requires Ruby {} (from {}) but no installed version matches.\nRun: railsup ruby install {}",
+                self.requirement, self.source, version
+            ),
+            (None, _) if self.no_match => format!(
+                "Project requires Ruby {} (from {}) but no available version matches.\nSee: railsup ruby list --available",
+                self.requirement, self.source
+            ),
+            (None, _) => format!(
+                "Project requires Ruby {} (from {}) but no installed version matches.\nInstall a matching version with: railsup ruby install <version>",
+                self.requirement, self.source
+            ),
+        };
+
+        if suggest_auto_install && self.candidate.is_some() {
+            message.push_str("\nOr set RAILSUP_AUTO_INSTALL=always to install it automatically.");
+        }
+
+        anyhow::anyhow!(message)
+    }
+}
+
+/// Check a project pin (exact version or requirement) against installed versions
+fn check_project(project: ResolvedRuby) -> Result<ProjectPin> {
+    let config = Config::load()?;
+    let pin = config.expand_alias(&project.version).to_string();
+    let requirement = VersionRequirement::parse(&pin)
+        .with_context(|| format!("Invalid Ruby version in {}", project.source))?;
+
+    if requirement.is_exact() {
+        if is_installed(&pin) {
+            return Ok(ProjectPin::Installed(ResolvedRuby {
+                version: pin,
+                source: project.source,
+            }));
+        }
+        return Ok(ProjectPin::Missing(MissingRuby {
+            requirement,
+            source: project.source,
+            candidate: Some(pin),
+            no_match: false,
+        }));
+    }
+
+    if let Some(version) = requirement.best_match(&list_installed_versions()?) {
+        return Ok(ProjectPin::Installed(ResolvedRuby {
+            version,
+            source: project.source,
+        }));
+    }
+
+    // Nothing installed matches - look for the newest downloadable match
+    let (candidate, no_match) = match requirement.latest_available() {
+        Ok(Some(version)) => (Some(version), false),
+        Ok(None) => (None, true),
+        Err(_) => (None, false),
+    };
+
+    Ok(ProjectPin::Missing(MissingRuby {
+        requirement,
+        source: project.source,
+        candidate,
+        no_match,
+    }))
+}
+
+/// Resolve the Ruby version ignoring project files
+/// Priority: global default -> latest installed
+pub fn resolve_global() -> Result<ResolvedRuby> {
+    let config = Config::load()?;
+    if let Some(default) = config.default_ruby() {
+        if let Ok(version) = resolve_installed(&config, default) {
+            return Ok(ResolvedRuby {
+                version,
+                source: VersionSource::GlobalDefault,
+            });
+        }
+    }
+
+    let installed = list_installed_versions()?;
+    if let Some(version) = installed.first() {
+        return Ok(ResolvedRuby {
+            version: version.clone(),
+            source: VersionSource::LatestInstalled,
+        });
+    }
+
+    bail!("No Ruby version installed.\nRun: railsup ruby install 4.0.1")
+}
+
+/// Resolve a version name (alias, channel, requirement or exact version) to
+/// an installed version
+pub fn resolve_installed(config: &Config, name: &str) -> Result<String> {
+    let target = config.expand_alias(name);
+    let requirement = VersionRequirement::parse(target)?;
+
+    let found = match &requirement {
+        VersionRequirement::Exact(version) => is_installed(version).then(|| version.clone()),
+        _ => requirement.best_match(&list_installed_versions()?),
+    };
+    if let Some(version) = found {
+        return Ok(version);
+    }
+
+    let label = if target == name.trim() {
+        target.to_string()
+    } else {
+        format!("{} ({})", name.trim(), target)
+    };
+    bail!(
+        "Ruby {} is not installed.\nRun: railsup ruby install {}",
+        label,
+        target
+    )
+}
+
+/// Check whether a Ruby version is installed under ~/.railsup/ruby
+pub fn is_installed(version: &str) -> bool {
+    paths::ruby_version_dir(version).exists()
+}
+
+/// Search up the directory tree for a project-pinned Ruby version
+pub fn find_project_ruby(start: &Path) -> Option<ResolvedRuby> {
+    let mut current = start.to_path_buf();
+
+    loop {
+        if let Some(found) = read_project_ruby(&current) {
+            return Some(found);
+        }
+
+        if !current.pop() {
+            return None;
+        }
+    }
+}
+
+/// Read a project-pinned Ruby version from a single directory (no walking)
+pub fn read_project_ruby(dir: &Path) -> Option<ResolvedRuby> {
+    // 1. railsup.toml
+    let railsup_toml = dir.join("railsup.toml");
+    if let Some(version) = read_file(&railsup_toml).and_then(|c| parse_railsup_toml(&c)) {
+        return Some(ResolvedRuby {
+            version,
+            source: VersionSource::RailsupToml(railsup_toml),
+        });
+    }
+
+    // 2. .ruby-version
+    let ruby_version = dir.join(".ruby-version");
+    if let Some(version) = read_file(&ruby_version).and_then(|c| parse_ruby_version_file(&c)) {
+        return Some(ResolvedRuby {
+            version,
+            source: VersionSource::RubyVersionFile(ruby_version),
+        });
+    }
+
+    // 3. .tool-versions (asdf, mise)
+    let tool_versions = dir.join(".tool-versions");
+    if let Some(version) = read_file(&tool_versions).and_then(|c| parse_tool_versions(&c)) {
+        return Some(ResolvedRuby {
+            version,
+            source: VersionSource::ToolVersions(tool_versions),
+        });
+    }
+
+    // 4. Gemfile `ruby` directive
+    let gemfile = dir.join("Gemfile");
+    if let Some(version) = read_file(&gemfile).and_then(|c| parse_gemfile_ruby(&c, dir)) {
+        return Some(ResolvedRuby {
+            version,
+            source: VersionSource::Gemfile(gemfile),
+        });
+    }
+
+    None
+}
+
+/// Rewrite an exact pin in a project file from `old` to `new`
+///
+/// Returns false (leaving the file alone) when the pin isn't a plain
+/// `old`, e.g. a requirement or a Gemfile `ruby file:` reference.
+pub fn rewrite_project_pin(source: &VersionSource, old: &str, new: &str) -> Result<bool> {
+    let Some(path) = source.path() else {
+        return Ok(false);
+    };
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    match rewrite_pin_content(source, &content, old, new) {
+        Some(updated) => {
+            fs::write(path, updated)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+fn rewrite_pin_content(
+    source: &VersionSource,
+    content: &str,
+    old: &str,
+    new: &str,
+) -> Option<String> {
+    let mut changed = false;
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            if changed {
+                return line.to_string();
+            }
+            let trimmed = line.trim();
+            let replaced = match source {
+                VersionSource::RubyVersionFile(_) => (trimmed == old
+                    || trimmed.strip_prefix("ruby-") == Some(old))
+                .then(|| line.replacen(old, new, 1)),
+                VersionSource::ToolVersions(_) => {
+                    let mut parts = trimmed.split_whitespace();
+                    (parts.next() == Some("ruby") && parts.next() == Some(old))
+                        .then(|| line.replacen(old, new, 1))
+                }
+                VersionSource::RailsupToml(_) | VersionSource::Gemfile(_) => {
+                    let quoted = [format!("\"{}\"", old), format!("'{}'", old)];
+                    quoted
+                        .iter()
+                        .find(|q| trimmed.starts_with("ruby") && trimmed.contains(q.as_str()))
+                        .map(|q| line.replacen(q.as_str(), &q.replace(old, new), 1))
+                }
+                VersionSource::GlobalDefault | VersionSource::LatestInstalled => None,
+            };
+            match replaced {
+                Some(line) => {
+                    changed = true;
+                    line
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect();
+
+    if !changed {
+        return None;
+    }
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    Some(updated)
+}
+
+fn read_file(path: &Path) -> Option<String> {
+    if !path.is_file() {
+        return None;
+    }
+    fs::read_to_string(path).ok()
+}
+
+/// Parse `ruby = "X"` from railsup.toml content
+pub fn parse_railsup_toml(content: &str) -> Option<String> {
+    let table = toml::from_str::<toml::Table>(content).ok()?;
+    let version = table.get("ruby")?.as_str()?.trim();
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.to_string())
+    }
+}
+
+/// Parse .ruby-version content ("3.3.0" or "ruby-3.3.0")
+pub fn parse_ruby_version_file(content: &str) -> Option<String> {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))?;
+    let version = line.trim_start_matches("ruby-");
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.to_string())
+    }
+}
+
+/// Parse the ruby entry from .tool-versions content ("ruby 3.3.0 [fallbacks...]")
+pub fn parse_tool_versions(content: &str) -> Option<String> {
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut parts = line.split_whitespace();
+        if parts.next() == Some("ruby") {
+            return parts
+                .next()
+                .map(|v| v.trim_start_matches("ruby-").to_string());
+        }
+    }
+    None
+}
+
+/// Parse the `ruby` directive from Gemfile content
+///
+/// Supports `ruby "3.3.0"`, `ruby '3.3.0'`, `ruby("3.3.0")` and
+/// `ruby file: ".ruby-version"` (resolved relative to `dir`).
+pub fn parse_gemfile_ruby(content: &str, dir: &Path) -> Option<String> {
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let rest = match trimmed
+            .strip_prefix("ruby ")
+            .or_else(|| trimmed.strip_prefix("ruby("))
+        {
+            Some(rest) => rest.trim_start(),
+            None => continue,
+        };
+
+        // ruby file: ".ruby-version"
+        if let Some(file_arg) = rest
+            .strip_prefix("file:")
+            .or_else(|| rest.strip_prefix(":file =>"))
+        {
+            let file = first_quoted(file_arg)?;
+            return read_file(&dir.join(file)).and_then(|c| parse_ruby_version_file(&c));
+        }
+
+        return first_quoted(rest).map(|v| v.trim_start_matches("ruby-").to_string());
+    }
+    None
+}
+
+/// Extract the first single- or double-quoted string from a line
+fn first_quoted(s: &str) -> Option<&str> {
+    let start = s.find(['"', '\''])?;
+    let quote = s[start..].chars().next()?;
+    let end = s[start + 1..].find(quote)?;
+    let value = &s[start + 1..start + 1 + end];
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn rewrite_pin_content_updates_exact_pins_only() {
+        let p = PathBuf::from("x");
+        let rv = VersionSource::RubyVersionFile(p.clone());
+        assert_eq!(
+            rewrite_pin_content(&rv, "ruby-3.4.1\n", "3.4.1", "3.4.2"),
+            Some("ruby-3.4.2\n".to_string())
+        );
+        assert_eq!(rewrite_pin_content(&rv, "3.4\n", "3.4.1", "3.4.2"), None);
+
+        let toml = VersionSource::RailsupToml(p.clone());
+        assert_eq!(
+            rewrite_pin_content(&toml, "# app\nruby = \"3.4.1\"\n", "3.4.1", "3.4.2"),
+            Some("# app\nruby = \"3.4.2\"\n".to_string())
+        );
+
+        let tools = VersionSource::ToolVersions(p.clone());
+        assert_eq!(
+            rewrite_pin_content(&tools, "nodejs 20.1.0\nruby 3.4.1\n", "3.4.1", "3.4.2"),
+            Some("nodejs 20.1.0\nruby 3.4.2\n".to_string())
+        );
+
+        let gemfile = VersionSource::Gemfile(p);
+        assert_eq!(
+            rewrite_pin_content(&gemfile, "source 'x'\nruby '3.4.1'\n", "3.4.1", "3.4.2"),
+            Some("source 'x'\nruby '3.4.2'\n".to_string())
+        );
+        assert_eq!(
+            rewrite_pin_content(&gemfile, "ruby file: \".ruby-version\"\n", "3.4.1", "3.4.2"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_railsup_toml_reads_ruby_key() {
+        assert_eq!(
+            parse_railsup_toml("ruby = \"3.4.1\"\n"),
+            Some("3.4.1".to_string())
+        );
+        assert_eq!(parse_railsup_toml("other = 1\n"), None);
+        assert_eq!(parse_railsup_toml("not toml ["), None);
+    }
+
+    #[test]
+    fn parse_ruby_version_file_strips_prefix() {
+        assert_eq!(
+            parse_ruby_version_file("3.3.6\n"),
+            Some("3.3.6".to_string())
+        );
+        assert_eq!(
+            parse_ruby_version_file("ruby-3.3.6\n"),
+            Some("3.3.6".to_string())
+        );
+        assert_eq!(parse_ruby_version_file("\n\n"), None);
+    }
+
+    #[test]
+    fn parse_tool_versions_finds_ruby_line() {
+        let content = "nodejs 20.1.0\nruby 3.3.6 3.2.2\n";
+        assert_eq!(parse_tool_versions(content), Some("3.3.6".to_string()));
+        assert_eq!(parse_tool_versions("nodejs 20.1.0\n"), None);
+    }
+
+    #[test]
+    fn parse_gemfile_ruby_quote_styles() {
+        let dir = Path::new("/nonexistent");
+        assert_eq!(
+            parse_gemfile_ruby("source \"https://rubygems.org\"\nruby \"3.3.0\"\n", dir),
+            Some("3.3.0".to_string())
+        );
+        assert_eq!(
+            parse_gemfile_ruby("ruby '3.4.1'\n", dir),
+            Some("3.4.1".to_string())
+        );
+        assert_eq!(
+            parse_gemfile_ruby("ruby(\"3.2.2\")\n", dir),
+            Some("3.2.2".to_string())
+        );
+        assert_eq!(parse_gemfile_ruby("gem \"rails\"\n", dir), None);
+    }
+
+    #[test]
+    fn parse_gemfile_ruby_file_directive() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join(".ruby-version"), "3.3.5\n").unwrap();
+        assert_eq!(
+            parse_gemfile_ruby("ruby file: \".ruby-version\"\n", dir.path()),
+            Some("3.3.5".to_string())
+        );
+    }
+
+    #[test]
+    fn read_project_ruby_precedence_within_directory() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("Gemfile"), "ruby \"3.1.0\"\n").unwrap();
+        std::fs::write(dir.path().join(".tool-versions"), "ruby 3.2.0\n").unwrap();
+        std::fs::write(dir.path().join(".ruby-version"), "3.3.0\n").unwrap();
+
+        let found = read_project_ruby(dir.path()).unwrap();
+        assert_eq!(found.version, "3.3.0");
+        assert_eq!(found.source.label(), ".ruby-version");
+
+        std::fs::write(dir.path().join("railsup.toml"), "ruby = \"3.4.0\"\n").unwrap();
+        let found = read_project_ruby(dir.path()).unwrap();
+        assert_eq!(found.version, "3.4.0");
+        assert_eq!(
+            found.source,
+            VersionSource::RailsupToml(dir.path().join("railsup.toml"))
+        );
+    }
+
+    #[test]
+    fn find_project_ruby_nearest_directory_wins() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("railsup.toml"), "ruby = \"3.4.0\"\n").unwrap();
+        let app = dir.path().join("apps/myapp");
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::write(app.join(".ruby-version"), "3.3.0\n").unwrap();
+
+        let found = find_project_ruby(&app).unwrap();
+        assert_eq!(found.version, "3.3.0");
+
+        let nested = app.join("app/models");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_project_ruby(&nested).unwrap().version, "3.3.0");
+    }
+
+    #[test]
+    fn read_project_ruby_returns_none_for_empty_dir() {
+        let dir = tempdir().unwrap();
+        assert!(read_project_ruby(dir.path()).is_none());
+    }
+
+    #[test]
+    fn version_source_display() {
+        assert_eq!(VersionSource::GlobalDefault.to_string(), "global default");
+        let source = VersionSource::RubyVersionFile(PathBuf::from("/app/.ruby-version"));
+        assert_eq!(source.to_string(), "/app/.ruby-version");
+        assert!(source.is_project());
+        assert!(!VersionSource::LatestInstalled.is_project());
+    }
+
+    #[test]
+    fn missing_ruby_error_suggests_auto_install() {
+        let missing = MissingRuby {
+            requirement: VersionRequirement::parse("3.4.2").unwrap(),
+            source: VersionSource::RubyVersionFile(PathBuf::from("/app/.ruby-version")),
+            candidate: Some("3.4.2".to_string()),
+            no_match: false,
+        };
+        let message = missing.into_error(true).to_string();
+        assert!(message.contains("Project requires Ruby 3.4.2 (from /app/.ruby-version)"));
+        assert!(message.contains("railsup ruby install 3.4.2"));
+        assert!(message.contains("RAILSUP_AUTO_INSTALL=always"));
+
+        let missing = MissingRuby {
+            requirement: VersionRequirement::parse("~> 9.9").unwrap(),
+            source: VersionSource::GlobalDefault,
+            candidate: None,
+            no_match: true,
+        };
+        let message = missing.into_error(true).to_string();
+        assert!(message.contains("no available version matches"));
+        assert!(!message.contains("RAILSUP_AUTO_INSTALL"));
+    }
+}
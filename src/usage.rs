@@ -0,0 +1,195 @@
+//! Registry of projects and the Ruby each one last used
+//!
+//! Stored in ~/.railsup/usage.json and updated by `exec`, `dev` and `new`.
+//! `ruby remove` warns about projects that still use a version, `ruby list`
+//! shows counts, and cleanup keeps Rubies a recent project still needs.
+
+use crate::paths;
+use crate::util::lock::FileLock;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Projects unused for longer than this no longer keep their Ruby alive
+pub const RECENT: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// One project and the Ruby it used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectUsage {
+    pub root: PathBuf,
+    pub ruby: String,
+    /// Unix timestamp (seconds)
+    pub last_used: u64,
+}
+
+/// Contents of usage.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub projects: Vec<ProjectUsage>,
+}
+
+impl Usage {
+    /// Load the registry; a missing or unreadable file is treated as empty
+    pub fn load() -> Self {
+        fs::read_to_string(paths::usage_file())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Load, change and save the registry while holding its lock, so
+    /// parallel runs don't drop each other's records
+    ///
+    /// Never waits: if another process holds the lock, nothing is changed
+    /// and this returns false.
+    pub fn try_update(change: impl FnOnce(&mut Usage)) -> Result<bool> {
+        let Some(_lock) = FileLock::try_acquire("usage")? else {
+            return Ok(false);
+        };
+        let mut usage = Usage::load();
+        change(&mut usage);
+        usage.write()?;
+        Ok(true)
+    }
+
+    /// Write the registry, replacing the file in one step (caller holds the lock)
+    fn write(&self) -> Result<()> {
+        let path = paths::usage_file();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(&tmp, content).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Note that the project at `root` just ran with `ruby`
+    pub fn record(&mut self, root: &Path, ruby: &str) {
+        self.record_at(root, ruby, unix_now());
+    }
+
+    fn record_at(&mut self, root: &Path, ruby: &str, now: u64) {
+        match self.projects.iter_mut().find(|p| p.root == root) {
+            Some(project) => {
+                project.ruby = ruby.to_string();
+                project.last_used = now;
+            }
+            None => self.projects.push(ProjectUsage {
+                root: root.to_path_buf(),
+                ruby: ruby.to_string(),
+                last_used: now,
+            }),
+        }
+    }
+
+    /// Projects that still exist and last used `version`, most recent first
+    pub fn projects_using(&self, version: &str) -> Vec<&ProjectUsage> {
+        let mut projects: Vec<_> = self
+            .projects
+            .iter()
+            .filter(|p| p.ruby == version && p.root.exists())
+            .collect();
+        projects.sort_by_key(|p| std::cmp::Reverse(p.last_used));
+        projects
+    }
+
+    /// Rubies used by projects that still exist and were used within `within`
+    pub fn versions_in_use(&self, within: Duration) -> BTreeSet<String> {
+        let cutoff = unix_now().saturating_sub(within.as_secs());
+        self.projects
+            .iter()
+            .filter(|p| p.last_used >= cutoff && p.root.exists())
+            .map(|p| p.ruby.clone())
+            .collect()
+    }
+}
+
+/// Record a project run, ignoring failures
+///
+/// Usage tracking is bookkeeping; it must never stop or hold up a command
+/// (parallel CI runs all exec at once), so a run that finds usage.json busy
+/// simply isn't recorded.
+pub fn record(root: &Path, ruby: &str) {
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let _ = Usage::try_update(|usage| usage.record(&root, ruby));
+}
+
+/// Format a "last used" timestamp relative to now ("today", "3 days ago")
+pub fn format_last_used(last_used: u64) -> String {
+    let days = unix_now().saturating_sub(last_used) / (24 * 60 * 60);
+    match days {
+        0 => "today".to_string(),
+        1 => "yesterday".to_string(),
+        n => format!("{} days ago", n),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn versions_in_use_skips_stale_and_missing_projects() {
+        let temp = TempDir::new().unwrap();
+        let now = unix_now();
+        let usage = Usage {
+            projects: vec![
+                ProjectUsage {
+                    root: temp.path().to_path_buf(),
+                    ruby: "3.4.1".to_string(),
+                    last_used: now,
+                },
+                ProjectUsage {
+                    root: temp.path().to_path_buf(),
+                    ruby: "3.2.0".to_string(),
+                    last_used: now - RECENT.as_secs() - 10,
+                },
+                ProjectUsage {
+                    root: temp.path().join("deleted"),
+                    ruby: "3.3.0".to_string(),
+                    last_used: now,
+                },
+            ],
+        };
+
+        let in_use = usage.versions_in_use(RECENT);
+        assert_eq!(in_use.into_iter().collect::<Vec<_>>(), vec!["3.4.1"]);
+    }
+
+    #[test]
+    fn record_updates_existing_project() {
+        let temp = TempDir::new().unwrap();
+        let app = temp.path().join("app");
+        let other = temp.path().join("other");
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(&other).unwrap();
+
+        let mut usage = Usage::default();
+        usage.record_at(&app, "3.3.6", 100);
+        usage.record_at(&other, "3.4.1", 200);
+        usage.record_at(&app, "3.4.1", 300);
+
+        assert_eq!(usage.projects.len(), 2);
+        let using: Vec<_> = usage
+            .projects_using("3.4.1")
+            .iter()
+            .map(|p| p.root.clone())
+            .collect();
+        assert_eq!(using, vec![app, other]);
+        assert!(usage.projects_using("3.3.6").is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+//! Advisory file locks shared between railsup processes
+//!
+//! Two terminals (or parallel CI jobs) can install the same Ruby or rewrite
+//! config.toml at the same time. Holding a lock under ~/.railsup/locks/
+//! serializes them; the lock is released when the guard is dropped or the
+//! process exits, so a crash never leaves it stuck.
+
+use crate::paths;
+use crate::util::ui;
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+
+/// Held lock; released on drop
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Lock `~/.railsup/locks/<name>.lock`, waiting for other processes
+    ///
+    /// `what` describes the protected work in the waiting message.
+    pub fn acquire(name: &str, what: &str) -> Result<Self> {
+        let path = paths::lock_file(name);
+        if let Some(lock) = Self::try_acquire_at(&path)? {
+            return Ok(lock);
+        }
+
+        ui::progress(&format!(
+            "Waiting for another railsup process ({})...",
+            what
+        ));
+        let file = open(&path)?;
+        lock(&file, true).with_context(|| format!("Failed to lock {}", path.display()))?;
+        Ok(Self { _file: file })
+    }
+
+    /// Lock `~/.railsup/locks/<name>.lock` if no other process holds it
+    pub fn try_acquire(name: &str) -> Result<Option<Self>> {
+        Self::try_acquire_at(&paths::lock_file(name))
+    }
+
+    /// Lock `path` if no other process holds it
+    pub fn try_acquire_at(path: &Path) -> Result<Option<Self>> {
+        let file = open(path)?;
+        match lock(&file, false) {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to lock {}", path.display())),
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open lock file {}", path.display()))
+}
+
+#[cfg(unix)]
+fn lock(file: &File, wait: bool) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut operation = libc::LOCK_EX;
+    if !wait {
+        operation |= libc::LOCK_NB;
+    }
+    loop {
+        // flock locks belong to the open file, so dropping `file` unlocks
+        if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+            return Ok(());
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn lock(_file: &File, _wait: bool) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    #[cfg(unix)]
+    fn second_lock_waits_until_first_is_dropped() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("locks/ruby-3.4.1.lock");
+
+        let first = FileLock::try_acquire_at(&path).unwrap();
+        assert!(first.is_some());
+        assert!(FileLock::try_acquire_at(&path).unwrap().is_none());
+
+        drop(first);
+        assert!(FileLock::try_acquire_at(&path).unwrap().is_some());
+    }
+}
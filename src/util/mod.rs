@@ -1,3 +1,6 @@
+pub mod disk;
+pub mod lock;
+pub mod port;
 pub mod process;
 pub mod tls;
 pub mod ui;
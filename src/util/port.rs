@@ -0,0 +1,134 @@
+//! TCP port checks for `railsup dev`
+
+use anyhow::{bail, Result};
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener};
+use std::process::Command;
+
+/// A process listening on a port
+#[derive(Debug, PartialEq, Eq)]
+pub struct PortHolder {
+    pub pid: u32,
+    pub command: String,
+}
+
+/// Fail with the process holding `port` if something already listens on it
+pub fn ensure_free(port: u16) -> Result<()> {
+    if is_free(port) {
+        return Ok(());
+    }
+
+    let holder = match holder(port) {
+        Some(holder) => format!(" by {} (pid {})", holder.command, holder.pid),
+        None => String::new(),
+    };
+    bail!(
+        "Port {} is already in use{}.\nStop it, or pick another port: railsup dev --port <port>",
+        port,
+        holder
+    )
+}
+
+/// Whether nothing listens on `port` on the loopback or wildcard address
+pub fn is_free(port: u16) -> bool {
+    let addrs: [SocketAddr; 3] = [
+        (Ipv4Addr::LOCALHOST, port).into(),
+        (Ipv4Addr::UNSPECIFIED, port).into(),
+        (Ipv6Addr::LOCALHOST, port).into(),
+    ];
+    // Only "in use" counts; a host without IPv6 can't bind ::1 at all
+    addrs
+        .iter()
+        .all(|addr| !matches!(TcpListener::bind(addr), Err(e) if e.kind() == ErrorKind::AddrInUse))
+}
+
+/// Find the process listening on `port` with lsof, or ss on Linux
+pub fn holder(port: u16) -> Option<PortHolder> {
+    let lsof = Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fpc"])
+        .output();
+    if let Some(found) = lsof
+        .ok()
+        .and_then(|out| parse_lsof(&String::from_utf8_lossy(&out.stdout)))
+    {
+        return Some(found);
+    }
+
+    let ss = Command::new("ss")
+        .args(["-Hltnp", &format!("sport = :{}", port)])
+        .output()
+        .ok()?;
+    parse_ss(&String::from_utf8_lossy(&ss.stdout))
+}
+
+/// Parse `lsof -F pc` output (`p<pid>` and `c<command>` lines)
+fn parse_lsof(output: &str) -> Option<PortHolder> {
+    let mut pid = None;
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix('p') {
+            pid = value.parse().ok();
+        } else if let (Some(command), Some(pid)) = (line.strip_prefix('c'), pid) {
+            return Some(PortHolder {
+                pid,
+                command: command.to_string(),
+            });
+        }
+    }
+    None
+}
+
+/// Parse `ss -p` output (`users:(("ruby",pid=4242,fd=7))`)
+fn parse_ss(output: &str) -> Option<PortHolder> {
+    let users = &output[output.find("users:((\"")? + 9..];
+    let (command, rest) = users.split_once('"')?;
+    let pid = rest.split_once("pid=")?.1;
+    let pid = pid[..pid.find(|c: char| !c.is_ascii_digit())?]
+        .parse()
+        .ok()?;
+    Some(PortHolder {
+        pid,
+        command: command.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bound_port_is_not_free() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(!is_free(port));
+        assert!(ensure_free(port)
+            .unwrap_err()
+            .to_string()
+            .contains("in use"));
+
+        drop(listener);
+        assert!(is_free(port));
+    }
+
+    #[test]
+    fn parses_lsof_and_ss_output() {
+        assert_eq!(
+            parse_lsof("p4242\ncruby\nf7\n"),
+            Some(PortHolder {
+                pid: 4242,
+                command: "ruby".to_string()
+            })
+        );
+        assert_eq!(parse_lsof(""), None);
+
+        let ss =
+            "LISTEN 0 1024 127.0.0.1:3000 0.0.0.0:* users:((\"puma 6.4.2 (tc\",pid=977,fd=7))\n";
+        assert_eq!(
+            parse_ss(ss),
+            Some(PortHolder {
+                pid: 977,
+                command: "puma 6.4.2 (tc".to_string()
+            })
+        );
+        assert_eq!(parse_ss("LISTEN 0 1024 127.0.0.1:3000 0.0.0.0:*\n"), None);
+    }
+}
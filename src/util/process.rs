@@ -1,5 +1,5 @@
-use anyhow::{Context, Result};
 use crate::util::tls;
+use anyhow::{Context, Result};
 use std::ffi::OsStr;
 use std::path::Path;
 use std::process::{Command, ExitStatus, Stdio};
@@ -15,6 +15,18 @@ pub fn warn(msg: &str) {
     eprintln!("  ⚠ {}", msg);
 }
 
+/// Print a progress message
+/// Uses stderr so it never ends up in captured command output
+pub fn progress(msg: &str) {
+    eprintln!("{}", msg);
+}
+
+/// Print a success message with checkmark as progress
+/// Uses stderr so it never ends up in captured command output
+pub fn progress_success(msg: &str) {
+    eprintln!("  ✓ {}", msg);
+}
+
 /// Print a dimmed/secondary message
 pub fn dim(msg: &str) {
     println!("  {}", msg);
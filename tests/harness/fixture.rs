@@ -2,6 +2,7 @@
 
 use std::fs;
 use std::path::PathBuf;
+use std::rc::Rc;
 use tempfile::TempDir;
 
 /// A test fixture representing a project structure
@@ -12,6 +13,13 @@ pub struct Fixture {
     pub path: PathBuf,
     /// Temp directory (if mutable fixture)
     _temp_dir: Option<TempDir>,
+    /// Isolated RAILSUP_HOME the binary runs against (never ~/.railsup)
+    pub home: PathBuf,
+    _home_dir: Rc<TempDir>,
+}
+
+fn temp_home() -> Rc<TempDir> {
+    Rc::new(TempDir::new().expect("Failed to create temp railsup home"))
 }
 
 impl Fixture {
@@ -23,10 +31,13 @@ impl Fixture {
 
         assert!(path.exists(), "Fixture not found: {}", name);
 
+        let home = temp_home();
         Self {
             name: name.to_string(),
             path,
             _temp_dir: None,
+            home: home.path().to_path_buf(),
+            _home_dir: home,
         }
     }
 
@@ -41,10 +52,13 @@ impl Fixture {
         let temp_dir = TempDir::new().expect("Failed to create temp directory");
         copy_dir_recursive(&source, temp_dir.path()).expect("Failed to copy fixture");
 
+        let home = temp_home();
         Self {
             name: name.to_string(),
             path: temp_dir.path().to_path_buf(),
             _temp_dir: Some(temp_dir),
+            home: home.path().to_path_buf(),
+            _home_dir: home,
         }
     }
 
@@ -57,10 +71,13 @@ impl Fixture {
             new_path.display()
         );
 
+        // Shares the parent's home so installed Rubies carry over
         Self {
             name: format!("{}/{}", self.name, subpath),
             path: new_path,
             _temp_dir: None,
+            home: self.home.clone(),
+            _home_dir: Rc::clone(&self._home_dir),
         }
     }
 }
@@ -3,10 +3,13 @@
 //! Provides fixture loading, binary execution, and custom assertions
 //! for testing end-to-end CLI behavior.
 
+// Not every test module uses every helper.
+#![allow(dead_code)]
+
 mod assertions;
 mod fixture;
 mod runner;
 
 pub use assertions::RailsupAssertions;
 pub use fixture::Fixture;
-pub use runner::{railsup, railsup_with_env, RunResult};
+pub use runner::{railsup, railsup_with_env, spawn_railsup, RunResult};
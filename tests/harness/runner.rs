@@ -3,7 +3,7 @@
 use super::Fixture;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::process::{Command, Output};
+use std::process::{Child, Command, Output, Stdio};
 
 /// Result of running the railsup binary
 #[derive(Debug)]
@@ -69,6 +69,8 @@ pub fn railsup_with_env(
     let mut cmd = Command::new(&binary);
     cmd.current_dir(&fixture.path);
     cmd.args(args);
+    cmd.env("RAILSUP_HOME", &fixture.home);
+    cmd.env_remove("RAILSUP_XDG");
 
     // Clear potentially interfering env vars
     cmd.env_remove("BUNDLE_GEMFILE");
@@ -86,13 +88,42 @@ pub fn railsup_with_env(
     RunResult::from(output)
 }
 
+/// Start railsup in a fixture without waiting for it (for long-running
+/// commands like `dev`); output is discarded
+pub fn spawn_railsup(fixture: &Fixture, args: &[&str]) -> Child {
+    let binary = PathBuf::from(env!("CARGO_BIN_EXE_railsup"));
+
+    let mut cmd = Command::new(&binary);
+    cmd.current_dir(&fixture.path);
+    cmd.args(args);
+    cmd.env("RAILSUP_HOME", &fixture.home);
+    cmd.env_remove("RAILSUP_XDG");
+
+    // Clear potentially interfering env vars
+    cmd.env_remove("BUNDLE_GEMFILE");
+    cmd.env_remove("RUBYOPT");
+    cmd.env_remove("RUBYLIB");
+    cmd.env_remove("GEM_HOME");
+    cmd.env_remove("GEM_PATH");
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start railsup")
+}
+
 /// Run railsup in a specific directory (not necessarily a fixture)
 pub fn railsup_in_dir(dir: &std::path::Path, args: &[&str]) -> RunResult {
     let binary = PathBuf::from(env!("CARGO_BIN_EXE_railsup"));
 
+    let home = tempfile::TempDir::new().expect("Failed to create temp railsup home");
+
     let mut cmd = Command::new(&binary);
     cmd.current_dir(dir);
     cmd.args(args);
+    cmd.env("RAILSUP_HOME", home.path());
+    cmd.env_remove("RAILSUP_XDG");
 
     // Clear potentially interfering env vars
     cmd.env_remove("BUNDLE_GEMFILE");
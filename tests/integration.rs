@@ -23,3 +23,6 @@ mod opt_out;
 
 #[path = "integration/error_messages.rs"]
 mod error_messages;
+
+#[path = "integration/process_cleanup.rs"]
+mod process_cleanup;
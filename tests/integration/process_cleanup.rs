@@ -0,0 +1,73 @@
+//! Process cleanup integration tests
+//!
+//! Verifies that stopping `railsup dev` takes down everything the Procfile
+//! processes started, not just their `sh -c` wrappers.
+
+use super::harness::{spawn_railsup, Fixture};
+use std::fs;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Whether `pid` is a live (non-zombie) process
+fn process_running(pid: &str) -> bool {
+    let output = Command::new("ps")
+        .args(["-o", "stat=", "-p", pid])
+        .output()
+        .expect("Failed to run ps");
+    let stat = String::from_utf8_lossy(&output.stdout);
+    !stat.trim().is_empty() && !stat.trim().starts_with('Z')
+}
+
+fn wait_for(timeout: Duration, mut done: impl FnMut() -> bool) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if done() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    false
+}
+
+#[test]
+#[ignore]
+#[cfg(unix)]
+fn sigint_leaves_no_orphan_processes() {
+    let fixture = Fixture::load_mutable("rails-8-app");
+    let pid_file = fixture.path.join("worker.pid");
+    fs::write(
+        fixture.path.join("Procfile.dev"),
+        "web: sleep 300 & echo $! > worker.pid; wait\nidle: sleep 300\n",
+    )
+    .unwrap();
+
+    let mut railsup = spawn_railsup(&fixture, &["dev"]);
+
+    // First run may install Ruby into the fixture's home
+    assert!(
+        wait_for(Duration::from_secs(300), || pid_file.exists()),
+        "Procfile processes never started"
+    );
+    thread::sleep(Duration::from_millis(200));
+    let grandchild = fs::read_to_string(&pid_file).unwrap().trim().to_string();
+    assert!(process_running(&grandchild));
+
+    Command::new("kill")
+        .args(["-INT", &railsup.id().to_string()])
+        .status()
+        .unwrap();
+
+    assert!(
+        wait_for(Duration::from_secs(15), || railsup
+            .try_wait()
+            .unwrap()
+            .is_some()),
+        "railsup dev did not exit after SIGINT"
+    );
+    assert!(
+        !process_running(&grandchild),
+        "sleep {} outlived railsup dev",
+        grandchild
+    );
+}